@@ -0,0 +1,105 @@
+use crate::common::Fpt;
+use crate::geom::Point;
+
+// spring stiffness (per engine step) for position and zoom; damping is
+// always 2*omega so the camera settles without overshooting
+const POS_OMEGA: Fpt = 0.12;
+const ZOOM_OMEGA: Fpt = 0.05;
+
+const LOOKAHEAD_STEPS: Fpt = 20.0;
+const LOOKAHEAD_MAX: Fpt = 100.0;
+
+const ZOOM_MIN: Fpt = 0.7;
+const ZOOM_MAX: Fpt = 1.25;
+const ZOOM_SPEED: Fpt = 8.0;
+const ZOOM_PAD_DIST: Fpt = 200.0;
+
+const AREA_MARGIN: Fpt = 30.0;
+
+#[derive(Clone)]
+pub struct Camera {
+	center: Point,
+	vel: Point,
+	zoom: Fpt,
+	zvel: Fpt,
+	view: Point,
+	area: Point,
+}
+
+impl Camera {
+	pub fn new(view: Point, area: Point, origin: Point) -> Self {
+		let mut center = Point::new(view.x() / 2.0, view.y() / 2.0);
+		center.add(&origin);
+		Self {
+			center,
+			vel: Point::new(0.0, 0.0),
+			zoom: 1.0,
+			zvel: 0.0,
+			view,
+			area,
+		}
+	}
+
+	fn damp(pos: Fpt, vel: Fpt, target: Fpt, omega: Fpt) -> (Fpt, Fpt) {
+		let acc = omega * omega * (target - pos) - 2.0 * omega * vel;
+		let vel = vel + acc;
+		(pos + vel, vel)
+	}
+
+	fn clamp_axis(c: Fpt, half: Fpt, size: Fpt) -> Fpt {
+		let lo = half - AREA_MARGIN;
+		let hi = size + AREA_MARGIN - half;
+		if lo > hi {
+			return size / 2.0;
+		}
+		Fpt::max(lo, Fpt::min(hi, c))
+	}
+
+	pub fn update(&mut self, ship: &Point, speed: &Point, pad: &Point) {
+		let mut ahead = speed.clone();
+		ahead.mul(LOOKAHEAD_STEPS);
+		if ahead.length() > LOOKAHEAD_MAX {
+			ahead.mul(LOOKAHEAD_MAX / ahead.length());
+		}
+		let mut target = ship.clone();
+		target.add(&ahead);
+
+		// zoom out when fast, zoom in when close to the pad
+		let fast = Fpt::min(speed.length() / ZOOM_SPEED, 1.0);
+		let mut dpad = pad.clone();
+		dpad.mul(-1.0);
+		dpad.add(ship);
+		let near = Fpt::max(0.0, 1.0 - dpad.length() / ZOOM_PAD_DIST);
+		let ztarget = 1.0 - (1.0 - ZOOM_MIN) * fast + (ZOOM_MAX - 1.0) * near * (1.0 - fast);
+
+		let (z, zv) = Self::damp(self.zoom, self.zvel, ztarget, ZOOM_OMEGA);
+		self.zoom = Fpt::max(ZOOM_MIN, Fpt::min(ZOOM_MAX, z));
+		self.zvel = zv;
+
+		let (x, vx) = Self::damp(self.center.x(), self.vel.x(), target.x(), POS_OMEGA);
+		let (y, vy) = Self::damp(self.center.y(), self.vel.y(), target.y(), POS_OMEGA);
+		let cx = Self::clamp_axis(x, self.width() / 2.0, self.area.x());
+		let cy = Self::clamp_axis(y, self.height() / 2.0, self.area.y());
+		// hitting the area border kills the velocity on that axis
+		self.vel = Point::new(if cx == x { vx } else { 0.0 }, if cy == y { vy } else { 0.0 });
+		self.center = Point::new(cx, cy);
+	}
+
+	pub fn zoom(&self) -> Fpt {
+		self.zoom
+	}
+	// visible world size
+	pub fn width(&self) -> Fpt {
+		self.view.x() / self.zoom
+	}
+	pub fn height(&self) -> Fpt {
+		self.view.y() / self.zoom
+	}
+	// world position of the top left corner of the view
+	pub fn origin(&self) -> Point {
+		Point::new(
+			self.center.x() - self.width() / 2.0,
+			self.center.y() - self.height() / 2.0
+		)
+	}
+}
@@ -31,6 +31,15 @@ macro_rules! attach {
 	};
 }
 
+#[macro_export]
+macro_rules! attach_window {
+	( $event:expr, $code:expr ) => {
+		let closure = Closure::<dyn FnMut(_)>::new($code);
+		window().add_event_listener_with_callback($event, closure.as_ref().unchecked_ref()).expect("Cannot attach event");
+		closure.forget();
+	};
+}
+
 #[macro_export]
 macro_rules! deg2rad {
 	($a:expr) => { ($a as f64) * f64::consts::PI / 180.0 }
@@ -1,56 +1,568 @@
 use std::f64;
 use crate::common::*;
 use crate::rand::Random;
-use crate::levels::GameData;
-use crate::geom::{Trig,Point,collide,inside_rect};
-use wasm_bindgen::JsValue;
+use crate::levels::{GameData,Pad};
+use crate::camera::Camera;
+use crate::geom::{Trig,Deg,Point,collide,inside_rect,inside_shape,nearest_edge};
 
+// pixels per step the arrows scroll at the nominal wind power
+const WIND_ARROW_SPEED: Fpt = 0.7;
+const WIND_ARROW_TILE: Fpt = 30.0;
+const WIND_GUST_DECAY: Fpt = 0.92;
+
+#[derive(Clone)]
+pub enum WindSchedule {
+	Constant,
+	// blows for `on` steps, then stops for `off` steps
+	Cycle { on: u32, off: u32, init: u32 },
+	// power oscillates between `min` and 1.0 times the nominal power
+	Sine { period: u32, min: Fpt, init: u32 },
+	// direction swings by up to `ampl` degrees around the nominal one
+	Sweep { period: u32, ampl: Deg, init: u32 },
+	// random gusts up to `boost` times the nominal power, started on
+	// average once every `every` steps
+	Gusts { every: u32, boost: Fpt, seed: u32 },
+}
+
+#[derive(Clone)]
 pub struct Wind {
 	shape: Vec<Point>,
 	power: Fpt,
-	orientation: i32,
-	accel: Option<Point>,
+	orientation: Deg,
+	schedule: WindSchedule,
+	cur_power: Fpt,
+	cur_dir: Deg,
+	gust: Fpt,
+	phase: Fpt,
+	rng: Option<Random>,
+	accel: Point,
 }
 
 impl Wind {
 	pub fn new(shape: Vec<Point>, power: Fpt, orientation: i32) -> Self {
+		Self::scheduled(shape, power, orientation, WindSchedule::Constant)
+	}
+	pub fn scheduled(shape: Vec<Point>, power: Fpt, orientation: i32, schedule: WindSchedule) -> Self {
+		let rng = match &schedule {
+			WindSchedule::Gusts { seed, .. } => Some(Random::seeded(*seed)),
+			_ => None
+		};
 		Self {
 			shape: shape,
 			power: power,
-			orientation: orientation,
-			accel: None,
+			orientation: orientation as Deg,
+			schedule,
+			cur_power: power,
+			cur_dir: orientation as Deg,
+			gust: 0.0,
+			phase: 0.0,
+			rng,
+			accel: Point::new(0.0, 0.0),
 		}
 	}
 	pub fn shape(&self) -> &Vec<Point> { &self.shape }
-	pub fn accel(&mut self, trig: &Trig) -> &Point {
-		match &self.accel {
-			None => {
-				let acc = Point::new(
-					self.power * trig.cos(self.orientation),
-					self.power * trig.sin(self.orientation)
-				);
-				self.accel = Some(acc);
+	pub fn move_step(&mut self, step: u32, trig: &Trig) {
+		let wave = |period: u32, init: u32| trig.sin(360.0 * ((step + init) % period.max(1)) as Deg / period.max(1) as Deg);
+		self.cur_power = self.power;
+		self.cur_dir = self.orientation;
+		match &self.schedule {
+			WindSchedule::Constant => {},
+			WindSchedule::Cycle { on, off, init } => {
+				if (step + init) % (on + off).max(1) >= *on {
+					self.cur_power = 0.0;
+				}
+			},
+			WindSchedule::Sine { period, min, init } => {
+				let k = min + (1.0 - min) * (wave(*period, *init) + 1.0) / 2.0;
+				self.cur_power = self.power * k;
+			},
+			WindSchedule::Sweep { period, ampl, init } => {
+				self.cur_dir = self.orientation + ampl * wave(*period, *init);
 			},
-			Some(_) => {}
+			WindSchedule::Gusts { every, boost, .. } => {
+				self.gust *= WIND_GUST_DECAY;
+				if let Some(rng) = self.rng.as_mut() {
+					if rng.rand(*every as i32) == 0 {
+						self.gust = Fpt::max(self.gust, boost * rng.nextfloat());
+					}
+				}
+				self.cur_power = self.power * (1.0 + self.gust);
+			},
+		}
+		self.accel = Point::new(
+			self.cur_power * trig.cos(self.cur_dir),
+			self.cur_power * trig.sin(self.cur_dir)
+		);
+		if self.power > 0.0 {
+			self.phase = (self.phase + WIND_ARROW_SPEED * self.cur_power / self.power) % WIND_ARROW_TILE;
+		}
+	}
+	pub fn accel(&self) -> &Point { &self.accel }
+	pub fn direction(&self) -> Deg { self.cur_dir }
+	// current power relative to the nominal one
+	pub fn strength(&self) -> Fpt {
+		if self.power > 0.0 { self.cur_power / self.power } else { 0.0 }
+	}
+	// offset of the arrow pattern along the wind direction
+	pub fn phase(&self) -> Fpt { self.phase }
+}
+
+// Region of liquid: the ship keeps only `drag` of its speed on each step
+// and `buoyancy` cancels that fraction of the local gravity.
+#[derive(Clone)]
+pub struct FluidZone {
+	shape: Vec<Point>,
+	drag: Fpt,
+	buoyancy: Fpt,
+}
+
+impl FluidZone {
+	pub fn new(shape: Vec<Point>, drag: Fpt, buoyancy: Fpt) -> Self {
+		Self {
+			shape,
+			drag,
+			buoyancy,
+		}
+	}
+	pub fn shape(&self) -> &Vec<Point> { &self.shape }
+}
+
+// points for landing, plus as much again with a full tank, times the
+// pad multiplier
+const LANDING_SCORE: Fpt = 1000.0;
+// fuel units per step flowing from a refuelling pad
+const REFUEL_RATE: Fpt = 4.0;
+const CARGO_SCORE: u32 = 500;
+const ASTEROID_SCORE: u32 = 50;
+const FOE_SCORE: u32 = 100;
+// steps after the shield pops in which hits only push the ship out
+const SHIELD_GRACE: u32 = 15;
+// fraction of the way a door moves in one step
+const DOOR_SPEED: Fpt = 0.02;
+// hostiles only use their own generator, so they behave the same every game
+const FOE_SEED: u32 = 0x2f6b_1d4d;
+
+#[derive(Clone)]
+struct Checkpoint {
+	pad: usize,
+	fuel: Fpt,
+}
+
+struct Contact {
+	wall: Option<usize>,
+	pad: Option<usize>,
+	angle: Deg,
+	vel: Point,
+	depth: Fpt,
+}
+
+// Point source of gravity, the pull is `strength` at `radius` (the
+// surface of the body) and decreases with the distance to the power of
+// `falloff`, 2 being newtonian.
+#[derive(Clone)]
+pub struct GravityWell {
+	pos: Point,
+	strength: Fpt,
+	falloff: Fpt,
+	radius: Fpt,
+}
+
+impl GravityWell {
+	pub fn new(pos: Point, strength: Fpt, falloff: Fpt, radius: Fpt) -> Self {
+		Self {
+			pos,
+			strength,
+			falloff,
+			radius,
+		}
+	}
+	pub fn position(&self) -> &Point { &self.pos }
+	pub fn strength(&self) -> Fpt { self.strength }
+	// distance at which the pull drops to `accel`
+	pub fn reach(&self, accel: Fpt) -> Fpt {
+		if self.strength <= accel || accel <= 0.0 {
+			return self.radius;
+		}
+		self.radius * (self.strength / accel).powf(1.0 / self.falloff)
+	}
+	pub fn pull(&self, p: &Point) -> Point {
+		let mut d = p.clone();
+		d.mul(-1.0);
+		d.add(&self.pos);
+		let dist = d.length();
+		if dist < 0.001 {
+			return Point::new(0.0, 0.0);
+		}
+		let acc = self.strength * (self.radius / Fpt::max(dist, self.radius)).powf(self.falloff);
+		d.mul(acc / dist);
+		d
+	}
+}
+
+#[derive(Clone)]
+pub enum PickupKind {
+	// fuel units
+	Fuel(Fpt),
+	// steps the shield lasts unless a collision uses it up
+	Shield(u32),
+	// points
+	Gem(u32),
+}
+
+#[derive(Clone)]
+pub struct Pickup {
+	kind: PickupKind,
+	pos: Point,
+	// steps before it shows up again once collected, never if 0
+	respawn: u32,
+	timer: u32,
+	available: bool,
+}
+
+impl Pickup {
+	const SIZE: Fpt = 7.0;
+
+	pub fn new(kind: PickupKind, pos: Point) -> Self {
+		Self {
+			kind,
+			pos,
+			respawn: 0,
+			timer: 0,
+			available: true,
+		}
+	}
+	pub fn respawning(mut self, steps: u32) -> Self {
+		self.respawn = steps;
+		self
+	}
+	pub fn kind(&self) -> &PickupKind { &self.kind }
+	pub fn position(&self) -> &Point { &self.pos }
+	pub fn available(&self) -> bool { self.available }
+	pub fn shape(&self) -> Vec<Point> {
+		vec![
+			Point::new(self.pos.x(), self.pos.y() - Self::SIZE),
+			Point::new(self.pos.x() + Self::SIZE, self.pos.y()),
+			Point::new(self.pos.x(), self.pos.y() + Self::SIZE),
+			Point::new(self.pos.x() - Self::SIZE, self.pos.y()),
+		]
+	}
+	fn collect(&mut self) {
+		self.available = false;
+		self.timer = self.respawn;
+	}
+	fn move_step(&mut self) {
+		if !self.available && self.timer > 0 {
+			self.timer -= 1;
+			self.available = self.timer == 0;
+		}
+	}
+}
+
+// pod the ship has to grab with its rope and set down on the pad
+#[derive(Clone)]
+pub struct Cargo {
+	pos: Point,
+	speed: Point,
+	// steps spent hovering within reach
+	latch: u32,
+	towed: bool,
+	// off the ground, it stays put until the rope gets taut
+	lifted: bool,
+	// where it sits in the frame of the pad once delivered
+	rest: Option<(usize, Point)>,
+}
+
+impl Cargo {
+	const SIZE: Fpt = 7.0;
+	const MASS: Fpt = 0.6;
+	const ROPE: Fpt = 45.0;
+	const REACH: Fpt = 50.0;
+	const LATCH_STEPS: u32 = 40;
+
+	fn new(pos: Point) -> Self {
+		Self {
+			pos,
+			speed: Point::new(0.0, 0.0),
+			latch: 0,
+			towed: false,
+			lifted: false,
+			rest: None,
+		}
+	}
+	pub fn is_towed(&self) -> bool { self.towed }
+	pub fn is_delivered(&self) -> bool { self.rest.is_some() }
+	// 0.0 to 1.0 while the rope is being attached
+	pub fn latching(&self) -> Fpt {
+		self.latch as Fpt / Self::LATCH_STEPS as Fpt
+	}
+	pub fn shape(&self) -> Vec<Point> {
+		let (x, y, sz) = (self.pos.x(), self.pos.y(), Self::SIZE);
+		vec![
+			Point::new(x - sz / 2.0, y - sz),
+			Point::new(x + sz / 2.0, y - sz),
+			Point::new(x + sz, y),
+			Point::new(x + sz / 2.0, y + sz),
+			Point::new(x - sz / 2.0, y + sz),
+			Point::new(x - sz, y),
+		]
+	}
+}
+
+// gun mounted on a wall, `facing` is where the barrel points at rest and
+// it can swing up to `arc` degrees to either side of it
+#[derive(Clone)]
+pub struct Turret {
+	pos: Point,
+	facing: Deg,
+	wall: Option<usize>,
+	arc: Deg,
+	turn_rate: Deg,
+	range: Fpt,
+	cooldown: u32,
+	// barrel angle relative to facing
+	aim: Deg,
+	timer: u32,
+	alive: bool,
+}
+
+impl Turret {
+	const SIZE: Fpt = 6.0;
+	const MUZZLE: Fpt = 12.0;
+	const BULLET_SPEED: Fpt = 3.5;
+
+	pub fn new(pos: Point, facing: Deg) -> Self {
+		Self {
+			pos,
+			facing,
+			wall: None,
+			arc: 80.0,
+			turn_rate: 1.5,
+			range: 260.0,
+			cooldown: 60,
+			aim: 0.0,
+			timer: 0,
+			alive: true,
+		}
+	}
+	// moves along with the given wall
+	pub fn on_wall(mut self, i: usize) -> Self {
+		self.wall = Some(i);
+		self
+	}
+	pub fn turning(mut self, rate: Deg) -> Self {
+		self.turn_rate = rate;
+		self
+	}
+	pub fn firing_every(mut self, steps: u32) -> Self {
+		self.cooldown = steps;
+		self
+	}
+	pub fn position(&self) -> &Point { &self.pos }
+	pub fn facing(&self) -> Deg { self.facing }
+	pub fn wall(&self) -> Option<usize> { self.wall }
+	pub fn aim(&self) -> Deg { self.aim }
+	pub fn is_alive(&self) -> bool { self.alive }
+	fn body(pos: &Point) -> Vec<Point> {
+		let sz = Self::SIZE;
+		vec![
+			Point::new(pos.x() - sz, pos.y() - sz),
+			Point::new(pos.x() + sz, pos.y() - sz),
+			Point::new(pos.x() + sz, pos.y() + sz),
+			Point::new(pos.x() - sz, pos.y() + sz),
+		]
+	}
+}
+
+// drifts towards the ship once it sees it and blows up on contact
+#[derive(Clone)]
+pub struct Mine {
+	pos: Point,
+	speed: Point,
+	range: Fpt,
+	alive: bool,
+}
+
+impl Mine {
+	const SIZE: Fpt = 6.0;
+	const ACCEL: Fpt = 0.015;
+	const MAX_SPEED: Fpt = 1.0;
+
+	pub fn new(pos: Point) -> Self {
+		Self {
+			pos,
+			speed: Point::new(0.0, 0.0),
+			range: 200.0,
+			alive: true,
+		}
+	}
+	pub fn sensing(mut self, range: Fpt) -> Self {
+		self.range = range;
+		self
+	}
+	pub fn is_alive(&self) -> bool { self.alive }
+	pub fn shape(&self) -> Vec<Point> {
+		let (x, y, sz) = (self.pos.x(), self.pos.y(), Self::SIZE);
+		vec![
+			Point::new(x, y - sz),
+			Point::new(x + sz * 0.7, y - sz * 0.7),
+			Point::new(x + sz, y),
+			Point::new(x + sz * 0.7, y + sz * 0.7),
+			Point::new(x, y + sz),
+			Point::new(x - sz * 0.7, y + sz * 0.7),
+			Point::new(x - sz, y),
+			Point::new(x - sz * 0.7, y - sz * 0.7),
+		]
+	}
+}
+
+#[derive(Clone)]
+pub enum Trigger {
+	// the ship flies into the shape
+	Zone(Vec<Point>),
+	// the ship rests on the platform wall
+	Landing(usize),
+}
+
+// turns the signal `id` on, doors and laser gates listen to signals
+#[derive(Clone)]
+pub struct Switch {
+	trigger: Trigger,
+	id: u32,
+	// steps it stays on once the ship leaves, forever if 0
+	hold: u32,
+	timer: u32,
+	on: bool,
+}
+
+impl Switch {
+	pub fn zone(shape: Vec<Point>, id: u32) -> Self {
+		Self { trigger: Trigger::Zone(shape), id, hold: 0, timer: 0, on: false }
+	}
+	pub fn landing(wall: usize, id: u32) -> Self {
+		Self { trigger: Trigger::Landing(wall), id, hold: 0, timer: 0, on: false }
+	}
+	pub fn for_steps(mut self, steps: u32) -> Self {
+		self.hold = steps;
+		self
+	}
+	pub fn trigger(&self) -> &Trigger { &self.trigger }
+	pub fn is_on(&self) -> bool { self.on }
+	fn activate(&mut self) {
+		self.on = true;
+		self.timer = self.hold;
+	}
+	fn move_step(&mut self) {
+		if self.on && self.hold > 0 {
+			self.timer = self.timer.saturating_sub(1);
+			self.on = self.timer > 0;
+		}
+	}
+}
+
+// beam between two points, lethal for `on` steps then off for `off`
+// steps
+#[derive(Clone)]
+pub struct LaserGate {
+	from: Point,
+	to: Point,
+	on: u32,
+	off: u32,
+	init: u32,
+	// signal that shuts it down
+	id: Option<u32>,
+}
+
+impl LaserGate {
+	pub fn new(from: Point, to: Point, on: u32, off: u32) -> Self {
+		assert!(on + off > 0, "laser gate cycle must not be empty");
+		Self { from, to, on, off, init: 0, id: None }
+	}
+	pub fn with_phase(mut self, init: u32) -> Self {
+		self.init = init;
+		self
+	}
+	pub fn switched(mut self, id: u32) -> Self {
+		self.id = Some(id);
+		self
+	}
+	pub fn ends(&self) -> (&Point, &Point) { (&self.from, &self.to) }
+	fn beam(&self) -> Vec<Point> {
+		vec![self.from.clone(), self.to.clone(), self.to.clone()]
+	}
+	fn cycle_on(&self, step: u32) -> bool {
+		(step + self.init) % (self.on + self.off) < self.on
+	}
+}
+
+// two linked zones, flying into one end comes out of the other with the
+// velocity turned by `turn` (by -turn going back)
+#[derive(Clone)]
+pub struct Portal {
+	a: Point,
+	b: Point,
+	radius: Fpt,
+	turn: Deg,
+}
+
+impl Portal {
+	pub fn new(a: Point, b: Point) -> Self {
+		Self { a, b, radius: 18.0, turn: 0.0 }
+	}
+	pub fn sized(mut self, radius: Fpt) -> Self {
+		self.radius = radius;
+		self
+	}
+	pub fn rotating(mut self, turn: Deg) -> Self {
+		self.turn = turn;
+		self
+	}
+	pub fn ends(&self) -> (&Point, &Point) { (&self.a, &self.b) }
+	pub fn radius(&self) -> Fpt { self.radius }
+	// entry, exit and turn when entering from the given end
+	fn route(&self, from_a: bool) -> (&Point, &Point, Deg) {
+		if from_a {
+			(&self.a, &self.b, self.turn)
+		} else {
+			(&self.b, &self.a, -self.turn)
 		}
-		self.accel.as_ref().unwrap()
 	}
-	pub fn direction(&self) -> i32 { self.orientation }
 }
 
+#[derive(Clone)]
+pub struct Projectile {
+	pos: Point,
+	speed: Point,
+	life: u32,
+}
+
+impl Projectile {
+	const SPEED: Fpt = 6.0;
+	const LIFE: u32 = 60;
+	// steps between two shots
+	const COOLDOWN: u32 = 8;
+
+	pub fn position(&self) -> &Point { &self.pos }
+	// segment covered in the last step, fast shots would skip thin walls
+	fn trail(&self) -> Vec<Point> {
+		let prev = Point::new(self.pos.x() - self.speed.x(), self.pos.y() - self.speed.y());
+		vec![prev, self.pos.clone(), self.pos.clone()]
+	}
+}
+
+#[derive(Clone)]
 pub struct Particle {
 	pos: Point,
 	dir: Point,
-	col: JsValue,
+	col: &'static str,
 	life: u32
 }
 
 impl Particle {
-	pub fn new(col: &str, pos: Point, dir: Point) -> Self {
+	pub fn new(col: &'static str, pos: Point, dir: Point) -> Self {
 		Self {
 			pos: pos,
 			dir: dir,
-			col: JsValue::from_str(col),
+			col,
 			life: 20
 		}
 	}
@@ -70,26 +582,95 @@ impl Particle {
 		}
 		return (self.life as f64) / 10.0;
 	}
-	pub fn color(&self) -> &JsValue {
-		&self.col
+	pub fn color(&self) -> &'static str {
+		self.col
 	}
 	pub fn position(&self) -> &Point {
 		&self.pos
 	}
 }
 
+const ASTEROID_BOUNCE: Fpt = 0.8;
+const ASTEROID_GRIP: Fpt = 0.3;
+
+// one side of a contact: where it is touched from its center and how
+// hard it is to move, walls have no inverse mass
+struct ContactBody {
+	arm: Point,
+	inv_mass: Fpt,
+	inv_inertia: Fpt,
+}
+
+impl ContactBody {
+	fn wall() -> Self {
+		Self { arm: Point::new(0.0, 0.0), inv_mass: 0.0, inv_inertia: 0.0 }
+	}
+	fn resist(&self, dir: &Point) -> Fpt {
+		let c = self.arm.cross(dir);
+		self.inv_mass + c * c * self.inv_inertia
+	}
+}
+
+// impulse b gets (a gets the opposite) when b moves at rel relative to
+// a at the contact, n is the normal from a to b; friction is limited by
+// the normal impulse and is what makes asteroids trade spin
+fn contact_impulse(rel: &Point, n: &Point, a: &ContactBody, b: &ContactBody) -> Option<Point> {
+	let vn = rel.dot(n);
+	if vn >= 0.0 {
+		return None;
+	}
+	let jn = -(1.0 + ASTEROID_BOUNCE) * vn / (a.resist(n) + b.resist(n));
+	let t = Point::new(-n.y(), n.x());
+	let jt = -rel.dot(&t) / (a.resist(&t) + b.resist(&t));
+	let jt = Fpt::max(-ASTEROID_GRIP * jn, Fpt::min(ASTEROID_GRIP * jn, jt));
+	Some(Point::new(jn * n.x() + jt * t.x(), jn * n.y() + jt * t.y()))
+}
+
+fn shifted(shape: &[Point], d: &Point) -> Vec<Point> {
+	shape.iter().map(|p| Point::new(p.x() + d.x(), p.y() + d.y())).collect()
+}
+
+#[derive(Clone)]
 pub struct Asteroid {
 	vert: Vec<Point>,
 	pos: Point,
-	rot: i32,
+	rot: Deg,
 	dpos: Point,
-	drot: i32,
+	drot: Deg,
+	radius: Fpt,
+	// 1.0 for a full size asteroid, halves roughly at each split
+	size: Fpt,
 }
 impl Asteroid {
-	pub fn move_step(&mut self, area: &Point) {
+	// a disc of uniform density, only the ratios matter
+	fn body(&self, arm: Point) -> ContactBody {
+		let mass = self.radius * self.radius;
+		ContactBody {
+			arm,
+			inv_mass: 1.0 / mass,
+			inv_inertia: 2.0 / (mass * self.radius * self.radius),
+		}
+	}
+	fn velocity_at(&self, arm: &Point) -> Point {
+		let w = self.drot.to_radians();
+		Point::new(self.dpos.x() - w * arm.y(), self.dpos.y() + w * arm.x())
+	}
+	fn push(&mut self, imp: &Point, body: &ContactBody) {
+		self.dpos.add(&Point::new(imp.x() * body.inv_mass, imp.y() * body.inv_mass));
+		self.drot += (body.arm.cross(imp) * body.inv_inertia).to_degrees();
+	}
+	pub fn move_step(&mut self, area: &Point, wrap: bool) {
 		self.pos.add(&self.dpos);
-		self.rot += self.drot;
+		self.rot = (self.rot + self.drot).rem_euclid(360.0);
 		const EXTRASIZE : Fpt = 30.0;
+		if wrap {
+			// fully out of sight before showing up on the other side
+			self.pos = Point::new(
+				(self.pos.x() + EXTRASIZE).rem_euclid(area.x() + 2.0 * EXTRASIZE) - EXTRASIZE,
+				(self.pos.y() + EXTRASIZE).rem_euclid(area.y() + 2.0 * EXTRASIZE) - EXTRASIZE
+			);
+			return;
+		}
 		if self.pos.x() < -EXTRASIZE && self.dpos.x() < 0.0 {
 			self.dpos.flipx();
 		}
@@ -125,51 +706,139 @@ impl Asteroid {
 	}
 }
 
+pub struct Trajectory {
+	path: Vec<Point>,
+	impact: Option<Point>,
+	// comes to rest on a pad or a platform
+	landed: bool,
+}
+impl Trajectory {
+	pub fn path(&self) -> &Vec<Point> { &self.path }
+	pub fn impact(&self) -> Option<&Point> { self.impact.as_ref() }
+	pub fn landed(&self) -> bool { self.landed }
+}
+
+#[derive(Clone)]
 pub struct GameEngine {
-	rot: i32,
+	rot: Deg,
 	pos: Point,
 	speed: Point,
-	thrust: bool,
+	throttle: Fpt,
 	block_alert: bool,
 	lrot: bool,
 	rrot: bool,
-	fuel: u32,
-	fuel_warn: u32,
+	torque: Fpt,
+	rot_vel: Fpt,
+	monoprop: Fpt,
+	fuel: Fpt,
+	fuel_warn: Fpt,
 	collided: bool,
 	landed: bool,
 	particles: Vec<Particle>,
 	asteroids: Vec<Asteroid>,
+	// gusts advance during the game, the config keeps them seeded for a
+	// restart
+	winds: Vec<Wind>,
+	// collected ones are back on a restart, the config keeps them as
+	// they were
+	pickups: Vec<Pickup>,
 	blownup: bool,
 	rng: Random,
 	trig: Trig,
 	step: u32,
-	viewport_pos: Option<Point>,
+	camera: Option<Camera>,
 	walls: Vec<Vec<Point>>,
+	prev_walls: Vec<Vec<Point>>,
+	pads: Vec<Vec<Point>>,
+	pad_vels: Vec<Point>,
+	pad_fuel: Vec<Fpt>,
+	landed_pad: Option<usize>,
+	// where the ship sits in the frame of the pad once landed
+	pad_rest: Option<Point>,
+	score: Option<u32>,
+	stage: usize,
+	checkpoint: Option<Checkpoint>,
+	mission_steps: u32,
+	shield: u32,
+	grace: u32,
+	bonus: u32,
+	cargo: Option<Cargo>,
+	ammo: u32,
+	firing: bool,
+	cooldown: u32,
+	projectiles: Vec<Projectile>,
+	bullets: Vec<Projectile>,
+	// hostiles as they move and get destroyed, the config keeps them as
+	// they were for a restart
+	turrets: Vec<Turret>,
+	mines: Vec<Mine>,
+	switches: Vec<Switch>,
+	// portal end the ship came out of and has not left yet, true for b
+	portal_lock: Option<(usize, bool)>,
+	// how far open each door wall is, 0.0 to 1.0
+	doors: Vec<Fpt>,
+	// where each turret currently sits and points at rest
+	mounts: Vec<(Point, Deg)>,
+	foe_rng: Random,
 	config: GameData
 }
 impl GameEngine {
 	pub fn new(cfg: GameData) -> Self {
 		let mut rv = Self {
-			rot: 0i32,
+			rot: 0.0,
 			pos: cfg.pos0.clone(),
 			speed: cfg.speed0.clone(),
-			thrust: false,
+			throttle: 0.0,
 			block_alert: false,
 			lrot: false, rrot: false,
-			fuel: cfg.initial_fuel as u32,
-			fuel_warn: (cfg.full_fuel / 5.0) as u32,
+			torque: 0.0, rot_vel: 0.0,
+			monoprop: match &cfg.handling { Some(h) => h.monoprop, None => 0.0 },
+			fuel: cfg.initial_fuel as Fpt,
+			fuel_warn: cfg.full_fuel / 5.0,
 			collided: false,
 			landed: false,
 			particles: Vec::new(),
 			asteroids: Vec::new(),
+			winds: cfg.winds.clone(),
+			pickups: cfg.pickups.clone(),
 			rng: Random::new(),
 			blownup: false,
 			trig: Trig::new(),
 			step: 0u32,
 			walls: vec!(),
-			viewport_pos: match &cfg.viewport_pos0 {
+			prev_walls: vec!(),
+			pads: vec!(),
+			pad_vels: vec!(),
+			pad_fuel: cfg.pads.iter().map(|p| p.fuel_stock()).collect(),
+			landed_pad: None,
+			pad_rest: None,
+			score: None,
+			stage: 0,
+			checkpoint: None,
+			mission_steps: 0,
+			shield: 0,
+			grace: 0,
+			bonus: 0,
+			cargo: cfg.cargo.clone().map(Cargo::new),
+			ammo: cfg.ammo,
+			firing: false,
+			cooldown: 0,
+			projectiles: vec!(),
+			bullets: vec!(),
+			turrets: cfg.turrets.clone(),
+			mines: cfg.mines.clone(),
+			switches: cfg.switches.clone(),
+			portal_lock: None,
+			doors: vec!(),
+			mounts: vec!(),
+			foe_rng: Random::seeded(FOE_SEED),
+			camera: match &cfg.viewport {
 				None => None,
-				Some(x) => Some(x.clone())
+				Some(v) => Some(Camera::new(
+					v.clone(),
+					cfg.area.clone(),
+					match &cfg.viewport_pos0 { Some(p) => p.clone(), None => Point::new(0.0, 0.0) }
+				))
 			},
 			config: cfg
 		};
@@ -179,9 +848,24 @@ impl GameEngine {
 		}
 
 		rv.walls = rv.config.get_walls(rv.step, &rv.trig);
+		rv.doors = rv.walls.iter().map(|_| 0.0).collect();
+		rv.prev_walls = rv.walls.clone();
+		rv.pads = rv.config.get_pads(rv.step, &rv.trig);
+		rv.pad_vels = rv.pads.iter().map(|_| Point::new(0.0, 0.0)).collect();
+		rv.mounts = rv.config.get_turrets(rv.step, &rv.trig);
+		// turrets do not all fire in sync
+		for t in rv.turrets.iter_mut() {
+			t.timer = rv.foe_rng.rand(t.cooldown as i32) as u32;
+		}
+		for w in rv.winds.iter_mut() {
+			w.move_step(rv.step, &rv.trig);
+		}
 
 		rv
 	}
+	pub fn restart(&mut self) {
+		*self = Self::new(self.config.clone());
+	}
 	pub fn fuel_warn(&self) -> bool {
 		self.fuel <= self.fuel_warn
 	}
@@ -192,27 +876,80 @@ impl GameEngine {
 	fn accel(&mut self, p: &Point) {
 		self.speed.add(p);
 	}
+	fn gravity_at(&self, p: &Point) -> Point {
+		let mut acc = self.config.gravity.clone();
+		for well in self.config.gravity_wells.iter() {
+			acc.add(&well.pull(p));
+		}
+		acc
+	}
 	fn apply_gravity(&mut self) {
-		self.speed.add(&self.config.gravity);
+		let acc = self.gravity_at(&self.pos);
+		self.speed.add(&acc);
 	}
 	fn apply_wind(&mut self, ship: &Vec<Point>) {
-		for wind in self.config.winds.iter_mut() {
+		// winds push with a force, a heavier ship drifts less
+		let ratio = self.mass_ratio();
+		for wind in self.winds.iter() {
 			if collide(&ship, wind.shape()) {
-				self.speed.add(wind.accel(&self.trig));
+				let mut acc = wind.accel().clone();
+				acc.mul(ratio);
+				self.speed.add(&acc);
+			}
+		}
+	}
+	fn fuel_mass(&self) -> Fpt {
+		self.config.fuel_mass
+	}
+	pub fn has_mass(&self) -> bool {
+		self.fuel_mass() > 0.0
+	}
+	// dry mass is 1.0, the fuel adds up to fuel_mass on a full tank
+	pub fn mass(&self) -> Fpt {
+		1.0 + self.fuel_mass() * self.fuel / self.config.full_fuel
+	}
+	// acceleration scale for a force, the level thrust and winds are
+	// tuned for a ship with half a tank
+	fn mass_ratio(&self) -> Fpt {
+		(1.0 + self.fuel_mass() / 2.0) / self.mass()
+	}
+	fn apply_fluids(&mut self, ship: &Vec<Point>) {
+		let gravity = self.gravity_at(&self.pos);
+		let ratio = self.mass_ratio();
+		let mut wet = false;
+		for fluid in self.config.fluids.iter() {
+			if collide(ship, fluid.shape()) {
+				wet = true;
+				let mut lift = gravity.clone();
+				lift.mul(-fluid.buoyancy * ratio);
+				self.speed.add(&lift);
+				self.speed.mul(fluid.drag);
+				self.rot_vel *= fluid.drag;
 			}
 		}
+		if wet && self.rng.nextbits(3) == 0 {
+			let pos = self.remap_ship(&Point::new(0.0, -10.0));
+			let dir = Point::new(0.2 * self.rng.sign() * self.rng.nextfloat(), -0.6 - 0.4 * self.rng.nextfloat());
+			self.particles.push(Particle::new("#9fd8ff", pos, dir));
+		}
+	}
+	pub fn iter_fluids<F>(&self, mut f: F) where F: FnMut(&FluidZone) {
+		for z in self.config.fluids.iter() {
+			f(z);
+		}
 	}
 	fn friction(&mut self, amt: Fpt) {
 		self.speed.mul(amt);
 	}
-	pub fn set_thrust(&mut self, value: bool) {
-		self.thrust = value;
+	pub fn set_throttle(&mut self, value: Fpt) {
+		self.throttle = Fpt::max(0.0, Fpt::min(1.0, value));
+	}
+	pub fn throttle(&self) -> Fpt {
+		self.throttle
 	}
-	pub fn rotate(&mut self, value: i32) {
+	pub fn rotate(&mut self, value: Deg) {
 		if ! self.landed {
-			let mut v = self.rot + value;
-			while v < 0 { v += 360; }
-			self.rot = v % 360;
+			self.rot = (self.rot + value).rem_euclid(360.0);
 		}
 	}
 	pub fn set_rotation(&mut self, left: Option<bool>, right: Option<bool>) {
@@ -223,135 +960,1089 @@ impl GameEngine {
 			self.rrot = v;
 		}
 	}
+	// analog rotation command, -1.0 (left) to 1.0 (right), only used
+	// with the attitude model
+	pub fn set_torque(&mut self, value: Fpt) {
+		self.torque = Fpt::max(-1.0, Fpt::min(1.0, value));
+	}
+	pub fn set_firing(&mut self, on: bool) {
+		self.firing = on;
+	}
+	pub fn has_weapons(&self) -> bool {
+		self.config.ammo > 0
+	}
+	pub fn ammo(&self) -> u32 {
+		self.ammo
+	}
+	pub fn has_inertia(&self) -> bool {
+		self.config.handling.is_some()
+	}
 	pub fn is_level(&self) -> bool {
+		let i = self.target_pad();
+		self.level_on(self.pad_angle(i), &self.pad_vels[i])
+	}
+	// attitude and speed are checked relative to a surface with the given
+	// slope moving at the given velocity
+	fn level_on(&self, angle: Deg, vel: &Point) -> bool {
+		self.level_at(&self.speed, angle, vel)
+	}
+	fn level_at(&self, speed: &Point, angle: Deg, vel: &Point) -> bool {
+		let rel_rot = (self.rot - angle + 180.0).rem_euclid(360.0) - 180.0;
+		let mut rel = vel.clone();
+		rel.mul(-1.0);
+		rel.add(speed);
+		let rel = self.trig.rot(&rel, -angle);
 		!self.blownup &&
-		(self.rot < self.config.levelling_rot || self.rot > (360 - self.config.levelling_rot)) &&
-			(self.speed.x().abs() < self.config.levelling_speed_x) &&
-			(self.speed.y().abs() < self.config.levelling_speed_y)
+		rel_rot.abs() < self.config.levelling_rot as Deg &&
+			(rel.x().abs() < self.config.levelling_speed_x) &&
+			(rel.y().abs() < self.config.levelling_speed_y)
 	}
-	fn apply_thrust(&mut self, amt: Fpt) {
-		if !self.blownup && self.thrust && self.fuel > 0 {
-			self.fuel -= 1;
-			self.speed.add(&Point::new(
-				amt * self.trig.sin(self.rot),
-				-amt * self.trig.cos(self.rot)
-			));
-
-			let drot = self.rot - 31 + (self.rng.nextbits(6) as i32);
-			let pt = self.remap_ship(&Point::new(0.0, 10.0));
-			let mut	delta = Point::new(
-				-0.5 * self.trig.sin(drot),
-				0.5 * self.trig.cos(drot)
-            );
-			delta.add(&self.speed);
-			self.particles.push(Particle::new("#fcdb03", pt, delta));
-		}
+	fn surface_angle(a: &Point, b: &Point) -> Deg {
+		(b.y() - a.y()).atan2(b.x() - a.x()).to_degrees()
 	}
-	fn apply_rotation(&mut self, amt: i32) {
-		let mut drot: i32 = 0;
-		if self.lrot {
-			drot -= amt;
+	// p in the frame of the surface from a to b: distance along it and
+	// depth below it
+	fn surface_local(&self, a: &Point, b: &Point, p: &Point) -> Point {
+		let mut d = a.clone();
+		d.mul(-1.0);
+		d.add(p);
+		self.trig.rot(&d, -Self::surface_angle(a, b))
+	}
+	fn surface_length(a: &Point, b: &Point) -> Fpt {
+		let mut d = a.clone();
+		d.mul(-1.0);
+		d.add(b);
+		d.length()
+	}
+	// deepest landing gear point within 5px under the surface, the middle
+	// of the ship must be over it
+	fn gear_contact(&self, a: &Point, b: &Point) -> Option<Fpt> {
+		self.gear_contact_at(&self.pos, a, b)
+	}
+	fn gear_contact_at(&self, pos: &Point, a: &Point, b: &Point) -> Option<Fpt> {
+		let len = Self::surface_length(a, b);
+		let mid = self.surface_local(a, b, &self.remap_at(pos, &Point::new(0.0, 10.0)));
+		if mid.x() < 0.0 || mid.x() > len {
+			return None;
 		}
-		if self.rrot {
-			drot += amt;
+		let mut rv = None;
+		for x in [-10.0, 0.0, 10.0].iter() {
+			let g = self.surface_local(a, b, &self.remap_at(pos, &Point::new(*x, 10.0)));
+			if g.x() >= 0.0 && g.x() <= len && g.y() >= 0.0 && g.y() <= 5.0 {
+				rv = Some(Fpt::max(rv.unwrap_or(0.0), g.y()));
+			}
 		}
-		self.rotate(drot);
+		rv
 	}
-	/* pub fn has_fuel(&self) -> bool {
-		self.fuel > 0
-	} */
-	pub fn has_collided(&self) -> bool {
-		self.collided
+	fn edge_vel(prev: &[Point], cur: &[Point]) -> Point {
+		if prev.len() < 2 || cur.len() < 2 {
+			return Point::new(0.0, 0.0);
+		}
+		Point::new(
+			(cur[0].x() - prev[0].x() + cur[1].x() - prev[1].x()) / 2.0,
+			(cur[0].y() - prev[0].y() + cur[1].y() - prev[1].y()) / 2.0
+		)
 	}
-	pub fn has_landed(&self) -> bool {
-		self.landed
+	fn pad_angle(&self, i: usize) -> Deg {
+		Self::surface_angle(&self.pads[i][0], &self.pads[i][1])
 	}
-	fn collision(&self, ship: &Vec<Point>) -> bool {
-		for p in ship.iter() {
-			if !inside_rect(&p, 0.0, 0.0, self.config.area.x(), self.config.area.y()) {
-				return true;
+	// in a mission only the last pad ends the game, once the stages
+	// before it are cleared
+	fn is_final_pad(&self, i: usize) -> bool {
+		match self.config.stages.last() {
+			Some(last) => *last == i && self.stage + 1 == self.config.stages.len(),
+			None => !self.config.pads[i].is_refuel()
+		}
+	}
+	// pad of the current stage, or the closest pad the game can end on
+	fn target_pad(&self) -> usize {
+		if let Some(i) = self.config.stages.get(self.stage) {
+			return *i;
+		}
+		let mut rv = 0;
+		let mut best = Fpt::MAX;
+		for (i, pad) in self.config.pads.iter().enumerate() {
+			let d = self.distance_to(&self.pad_center_of(i));
+			if self.is_final_pad(i) && !pad.is_refuel() && d < best {
+				best = d;
+				rv = i;
 			}
 		}
-		for obs in self.obs_shape().iter() {
-			if collide(&ship, &obs) {
-				return true;
+		rv
+	}
+	// platforms and refuelling pads the ship gently rests on
+	fn rest_contacts(&self) -> Vec<Contact> {
+		let mut rv = vec!();
+		for (i, shape) in self.walls.iter().enumerate() {
+			if !self.config.is_platform(i) || shape.len() < 2 {
+				continue;
+			}
+			let vel = Self::edge_vel(&self.prev_walls[i], shape);
+			let angle = Self::surface_angle(&shape[0], &shape[1]);
+			if !self.level_on(angle, &vel) {
+				continue;
+			}
+			if let Some(depth) = self.gear_contact(&shape[0], &shape[1]) {
+				rv.push(Contact { wall: Some(i), pad: None, angle, vel, depth });
 			}
 		}
-		for ast in self.asteroids.iter() {
-			if collide(&ship, &ast.remap4coll(&self.trig)) {
-				return true;
+		for (i, shape) in self.pads.iter().enumerate() {
+			if self.is_final_pad(i) {
+				continue;
+			}
+			let vel = self.pad_vels[i].clone();
+			let angle = self.pad_angle(i);
+			if !self.level_on(angle, &vel) {
+				continue;
+			}
+			if let Some(depth) = self.gear_contact(&shape[0], &shape[1]) {
+				rv.push(Contact { wall: None, pad: Some(i), angle, vel, depth });
 			}
 		}
-		if collide(&ship, &self.land_shape()) {
-			return true;
+		rv
+	}
+	// landing on the pad of the current stage saves the checkpoint, fuel
+	// comes from the pad stock as on any refuelling pad
+	fn reach_stage(&mut self, contacts: &[Contact]) {
+		let cur = match self.config.stages.get(self.stage) {
+			Some(i) => *i,
+			None => return
+		};
+		if contacts.iter().any(|c| c.pad == Some(cur)) {
+			self.checkpoint = Some(Checkpoint { pad: cur, fuel: self.fuel });
+			self.stage += 1;
 		}
-		false
 	}
-	fn landing(&self) -> bool {
-		if !self.is_level() || self.blownup {
-			return false;
+	fn respawn(&mut self) {
+		let cp = match &self.checkpoint {
+			Some(cp) => cp.clone(),
+			None => return
+		};
+		let mut pos = self.pad_center_of(cp.pad);
+		pos.add(&Point::new(0.0, -11.0));
+		self.pos = pos;
+		self.rot = self.pad_angle(cp.pad).rem_euclid(360.0);
+		self.rot_vel = 0.0;
+		self.speed = self.pad_vels[cp.pad].clone();
+		self.fuel = cp.fuel;
+		self.throttle = 0.0;
+		self.lrot = false;
+		self.rrot = false;
+		self.torque = 0.0;
+		self.shield = 0;
+		self.grace = 0;
+		self.landed_pad = None;
+		self.pad_rest = None;
+		self.blownup = false;
+		self.collided = false;
+		if !self.cargo_delivered() {
+			self.cargo = self.config.cargo.clone().map(Cargo::new);
 		}
-		let touch = self.remap_ship(&Point::new(0.0, 10.0));
-		if inside_rect(&touch, self.config.target_x0, self.config.target_y, self.config.target_x1, self.config.target_y + 5.0) {
-			return true;
+	}
+	fn collect_pickups(&mut self, ship: &Vec<Point>) {
+		let mut got = vec!();
+		for p in self.pickups.iter_mut() {
+			if p.available() && collide(ship, &p.shape()) {
+				p.collect();
+				got.push((p.kind().clone(), p.position().clone()));
+			}
 		}
-		if touch.x() < self.config.target_x0 || touch.x() > self.config.target_x1 {
-			return false;
+		for (kind, pos) in got.into_iter() {
+			let col = match kind {
+				PickupKind::Fuel(amt) => {
+					self.fuel = Fpt::min(self.config.full_fuel, self.fuel + amt);
+					"#ffd23f"
+				},
+				PickupKind::Shield(steps) => {
+					self.shield = u32::max(self.shield, steps);
+					"#66ccff"
+				},
+				PickupKind::Gem(points) => {
+					self.bonus += points;
+					"#ff66ff"
+				},
+			};
+			for _ in 0..12 {
+				let dir = Point::new(self.rng.sign() * self.rng.nextfloat(), self.rng.sign() * self.rng.nextfloat());
+				self.particles.push(Particle::new(col, pos.clone(), dir));
+			}
 		}
-		let touch = self.remap_ship(&Point::new(-10.0, 10.0));
-		if inside_rect(&touch, self.config.target_x0, self.config.target_y, self.config.target_x1, self.config.target_y + 5.0) {
-			return true;
+	}
+	// the shield takes the hit: bounce back off whatever was touched, for
+	// a little while anything still touching only pushes the ship out
+	fn absorb_collision(&mut self, resting: &[Contact]) {
+		if self.shield > 0 {
+			self.shield = 0;
+			self.grace = SHIELD_GRACE;
+			self.speed.mul(-0.5);
+			let pos = self.pos.clone();
+			for _ in 0..20 {
+				let dir = Point::new(self.rng.sign() * self.rng.nextfloat(), self.rng.sign() * self.rng.nextfloat());
+				self.particles.push(Particle::new("#66ccff", pos.clone(), dir));
+			}
 		}
-		let touch = self.remap_ship(&Point::new(10.0, 10.0));
-		inside_rect(&touch, self.config.target_x0, self.config.target_y, self.config.target_x1, self.config.target_y + 5.0)
+		self.push_out(resting);
 	}
-
-	fn blowup(&mut self) {
-		let base = self.remap_ship(&Point::new(0.0,0.0));
-		for _ in 0..200 {
-			let x = base.x() - 15.0 + (self.rng.nextbits(5) as Fpt);
-			let y = base.y() - 15.0 + (self.rng.nextbits(5) as Fpt);
-			let dx = (x-base.x()) / ((self.rng.nextbits(3) + 5) as Fpt) + self.speed.x();
-			let dy = (y-base.y()) / ((self.rng.nextbits(3) + 5) as Fpt) + self.speed.y();
-			self.particles.push(Particle::new("#42a4f5", Point::new(x,y), Point::new(dx,dy)));
+	// a pixel at a time away from the closest edge of everything the ship
+	// sinks into, then moving away at least as fast as it got pushed
+	fn push_out(&mut self, resting: &[Contact]) {
+		let mut moved = Point::new(0.0, 0.0);
+		for _ in 0..40 {
+			let ship = self.ship_shape();
+			if !self.collision(&ship, resting) {
+				break;
+			}
+			let center = self.remap_ship(&Point::new(0.0, 0.0));
+			let mut dir = Point::new(0.0, 0.0);
+			for d in self.wrap_shifts(&ship).iter() {
+				let (ship, center) = (shifted(&ship, d), Point::new(center.x() + d.x(), center.y() + d.y()));
+				let outlines = self.walls.iter().chain(self.pads.iter()).cloned()
+					.chain(self.asteroids.iter().map(|a| a.remap(&self.trig)))
+					.chain(self.config.lasers.iter().filter(|l| self.laser_on(l)).map(|l| l.beam()));
+				for shape in outlines.filter(|o| o.len() > 1 && collide(&ship, o)) {
+					if let Some((_, _, q)) = nearest_edge(&shape, &center) {
+						let mut n = Point::new(center.x() - q.x(), center.y() - q.y());
+						let len = n.length();
+						if len > 0.0 {
+							n.mul(if inside_shape(&shape, &center) { -1.0 / len } else { 1.0 / len });
+							dir.add(&n);
+						}
+					}
+				}
+			}
+			if !self.config.wrap && !inside_rect(&center, 20.0, 20.0, self.config.area.x() - 20.0, self.config.area.y() - 20.0) {
+				dir.add(&Point::new(self.config.area.x() / 2.0 - center.x(), self.config.area.y() / 2.0 - center.y()));
+			}
+			let len = dir.length();
+			if len == 0.0 {
+				break;
+			}
+			dir.mul(1.0 / len);
+			self.pos.add(&dir);
+			moved.add(&dir);
+		}
+		let dist = moved.length();
+		if dist > 0.0 {
+			moved.mul(1.0 / dist);
+			let vn = self.speed.dot(&moved);
+			if vn < dist + 0.5 {
+				moved.mul(dist + 0.5 - vn);
+				self.speed.add(&moved);
+			}
 		}
 	}
-
-	pub fn get_wind_step(&self) -> u32 {
-		if self.step % 30 < 15 {
-			return 0u32;
+	fn refuel(&mut self, contacts: &[Contact]) {
+		for c in contacts.iter() {
+			if let Some(i) = c.pad {
+				let amt = Fpt::min(REFUEL_RATE, Fpt::min(self.pad_fuel[i], self.config.full_fuel - self.fuel));
+				if amt > 0.0 {
+					self.pad_fuel[i] -= amt;
+					self.fuel += amt;
+				}
+			}
 		}
-		1u32
 	}
-
-	pub fn move_step(&mut self) {
+	// standing on a platform: no sinking in and moving along with it
+	fn apply_platforms(&mut self, contacts: &[Contact]) {
+		for c in contacts.iter() {
+			let vel = &c.vel;
+			let n = self.trig.rot(&Point::new(0.0, -1.0), c.angle);
+			let mut rel = vel.clone();
+			rel.mul(-1.0);
+			rel.add(&self.speed);
+			let vn = rel.x() * n.x() + rel.y() * n.y();
+			if vn < 0.0 {
+				let mut out = n.clone();
+				out.mul(-vn);
+				rel.add(&out);
+				rel.mul(0.8);
+			}
+			rel.add(vel);
+			self.speed = rel;
+			let mut out = n.clone();
+			out.mul(c.depth);
+			self.pos.add(&out);
+		}
+	}
+	fn apply_thrust(&mut self, amt: Fpt) {
+		if !self.blownup && self.throttle > 0.0 && self.fuel > 0.0 {
+			// one fuel unit per step at full throttle
+			let burn = Fpt::min(self.throttle, self.fuel);
+			let amt = amt * self.mass_ratio();
+			self.fuel -= burn;
+			self.speed.add(&Point::new(
+				burn * amt * self.trig.sin(self.rot),
+				-burn * amt * self.trig.cos(self.rot)
+			));
+			if self.rng.nextfloat() > burn {
+				return;
+			}
+
+			let drot = self.rot - 31.0 + (self.rng.nextbits(6) as Deg);
+			let pt = self.remap_ship(&Point::new(0.0, 10.0));
+			let mut	delta = Point::new(
+				-0.5 * self.trig.sin(drot),
+				0.5 * self.trig.cos(drot)
+            );
+			delta.add(&self.speed);
+			self.particles.push(Particle::new("#fcdb03", pt, delta));
+		}
+	}
+	fn fire(&mut self) {
+		self.cooldown = self.cooldown.saturating_sub(1);
+		if !self.firing || self.cooldown > 0 || self.ammo == 0 || self.blownup {
+			return;
+		}
+		self.ammo -= 1;
+		self.cooldown = Projectile::COOLDOWN;
+		let mut speed = Point::new(
+			Projectile::SPEED * self.trig.sin(self.rot),
+			-Projectile::SPEED * self.trig.cos(self.rot)
+		);
+		speed.add(&self.speed);
+		self.projectiles.push(Projectile {
+			pos: self.remap_ship(&Point::new(0.0, -20.0)),
+			speed,
+			life: Projectile::LIFE,
+		});
+	}
+	fn move_projectiles(&mut self) {
+		let mut hits = vec!();
+		for p in self.projectiles.iter_mut() {
+			p.pos.add(&p.speed);
+			p.life -= 1;
+			if self.config.wrap {
+				p.pos = Point::new(p.pos.x().rem_euclid(self.config.area.x()), p.pos.y().rem_euclid(self.config.area.y()));
+			}
+			let trail = p.trail();
+			if !inside_rect(&p.pos, 0.0, 0.0, self.config.area.x(), self.config.area.y()) ||
+				self.walls.iter().chain(self.pads.iter()).any(|w| collide(&trail, w)) {
+				p.life = 0;
+				continue;
+			}
+			if let Some(i) = self.asteroids.iter().position(|a| collide(&trail, &a.remap4coll(&self.trig))) {
+				p.life = 0;
+				hits.push((i, p.speed.clone()));
+				continue;
+			}
+			// a shot takes out one foe at most
+			if let Some(m) = self.mines.iter_mut().find(|m| m.alive && collide(&trail, &m.shape())) {
+				m.alive = false;
+				p.life = 0;
+				self.bonus += FOE_SCORE;
+				continue;
+			}
+			if let Some((t, _)) = self.turrets.iter_mut().zip(self.mounts.iter())
+				.find(|(t, (pos, _))| t.alive && collide(&trail, &Turret::body(pos))) {
+				t.alive = false;
+				p.life = 0;
+				self.bonus += FOE_SCORE;
+			}
+		}
+		self.projectiles.retain(|p| p.life > 0);
+
+		// children are appended, so removing from the back keeps the
+		// other indices valid
+		hits.sort_by_key(|h| std::cmp::Reverse(h.0));
+		hits.dedup_by_key(|h| h.0);
+		for (i, speed) in hits.into_iter() {
+			let ast = self.asteroids.swap_remove(i);
+			self.split_asteroid(ast, &speed);
+		}
+	}
+	// big asteroids break in two smaller ones flying apart, small ones
+	// are destroyed
+	fn split_asteroid(&mut self, ast: Asteroid, hit: &Point) {
+		for _ in 0..(30.0 * ast.size) as u32 {
+			let dir = Point::new(self.rng.sign() * self.rng.nextfloat() + ast.dpos.x(), self.rng.sign() * self.rng.nextfloat() + ast.dpos.y());
+			self.particles.push(Particle::new("#b88b2c", ast.pos.clone(), dir));
+		}
+		if ast.size < 0.5 {
+			self.bonus += ASTEROID_SCORE;
+			return;
+		}
+		// a shot fired backwards at its own speed can stand still
+		let axis = if hit.length() > 0.01 {
+			hit.clone()
+		} else if ast.dpos.length() > 0.01 {
+			ast.dpos.clone()
+		} else {
+			Point::new(0.0, 1.0)
+		};
+		let len = axis.length();
+		let side = Point::new(-axis.y() / len, axis.x() / len);
+		for s in [-1.0, 1.0].iter() {
+			let size = ast.size * 0.55;
+			let vert = self.asteroid_vertices(size);
+			let mut pos = ast.pos.clone();
+			pos.add(&Point::new(side.x() * s * ast.radius * 0.4, side.y() * s * ast.radius * 0.4));
+			let mut dpos = ast.dpos.clone();
+			dpos.add(&Point::new(side.x() * s * 0.6 + hit.x() * 0.05, side.y() * s * 0.6 + hit.y() * 0.05));
+			let radius = vert.iter().map(|p| p.length()).fold(0.0, Fpt::max);
+			self.asteroids.push(Asteroid {
+				vert,
+				pos,
+				rot: ast.rot,
+				dpos,
+				drot: ast.drot * s * 1.5,
+				radius,
+				size,
+			});
+		}
+	}
+	fn line_of_sight(&self, from: &Point, to: &Point) -> bool {
+		let seg = vec![from.clone(), to.clone(), to.clone()];
+		!self.walls.iter().any(|w| collide(&seg, w))
+	}
+	fn move_hostiles(&mut self) {
+		let hunting = !self.blownup && !self.landed;
+		for i in 0..self.turrets.len() {
+			let (pos, facing) = self.mounts[i].clone();
+			let t = &self.turrets[i];
+			if !t.alive {
+				continue;
+			}
+			let muzzle = Point::new(pos.x() + Turret::MUZZLE * self.trig.sin(facing), pos.y() - Turret::MUZZLE * self.trig.cos(facing));
+			let dx = self.pos.x() - muzzle.x();
+			let dy = self.pos.y() - muzzle.y();
+			let sees = hunting && dx.hypot(dy) < t.range && self.line_of_sight(&muzzle, &self.pos);
+			let target = if sees {
+				let rel = (dx.atan2(-dy).to_degrees() - facing + 180.0).rem_euclid(360.0) - 180.0;
+				Fpt::max(-t.arc, Fpt::min(t.arc, rel))
+			} else {
+				0.0
+			};
+
+			let t = &mut self.turrets[i];
+			t.aim += Fpt::max(-t.turn_rate, Fpt::min(t.turn_rate, target - t.aim));
+			t.timer = t.timer.saturating_sub(1);
+			if !sees || t.timer > 0 || (target - t.aim).abs() > 3.0 {
+				continue;
+			}
+			t.timer = t.cooldown;
+			let dir = facing + t.aim + (self.foe_rng.nextfloat() - 0.5) * 4.0;
+			let tip = Point::new(pos.x() + Turret::MUZZLE * self.trig.sin(facing + t.aim), pos.y() - Turret::MUZZLE * self.trig.cos(facing + t.aim));
+			self.bullets.push(Projectile {
+				pos: tip,
+				speed: Point::new(Turret::BULLET_SPEED * self.trig.sin(dir), -Turret::BULLET_SPEED * self.trig.cos(dir)),
+				life: Projectile::LIFE * 2,
+			});
+		}
+
+		for i in 0..self.mines.len() {
+			let m = &self.mines[i];
+			if !m.alive {
+				continue;
+			}
+			// hostile_hit only runs in flight, so once the ship is down
+			// the mines stay where they are
+			if self.landed {
+				self.mines[i].speed = Point::new(0.0, 0.0);
+				continue;
+			}
+			let dx = self.pos.x() - m.pos.x();
+			let dy = self.pos.y() - m.pos.y();
+			let dist = dx.hypot(dy);
+			let sees = hunting && dist > 0.0 && dist < m.range && self.line_of_sight(&m.pos, &self.pos);
+			let wobble = Point::new(0.005 * (self.foe_rng.nextfloat() - 0.5), 0.005 * (self.foe_rng.nextfloat() - 0.5));
+			let m = &mut self.mines[i];
+			if sees {
+				m.speed.add(&Point::new(Mine::ACCEL * dx / dist, Mine::ACCEL * dy / dist));
+				let v = m.speed.length();
+				if v > Mine::MAX_SPEED {
+					m.speed.mul(Mine::MAX_SPEED / v);
+				}
+			} else {
+				m.speed.mul(0.97);
+			}
+			m.speed.add(&wobble);
+			m.pos.add(&m.speed);
+			let shape = m.shape();
+			if self.walls.iter().chain(self.pads.iter()).any(|w| collide(&shape, w)) {
+				m.pos.add(&Point::new(-m.speed.x(), -m.speed.y()));
+				m.speed.mul(-0.5);
+			}
+		}
+
+		for b in self.bullets.iter_mut() {
+			b.pos.add(&b.speed);
+			b.life -= 1;
+			if self.config.wrap {
+				b.pos = Point::new(b.pos.x().rem_euclid(self.config.area.x()), b.pos.y().rem_euclid(self.config.area.y()));
+			}
+			let trail = b.trail();
+			if !inside_rect(&b.pos, 0.0, 0.0, self.config.area.x(), self.config.area.y()) ||
+				self.walls.iter().chain(self.pads.iter()).any(|w| collide(&trail, w)) ||
+				self.asteroids.iter().any(|a| collide(&trail, &a.remap4coll(&self.trig))) {
+				b.life = 0;
+			}
+		}
+		self.bullets.retain(|b| b.life > 0);
+	}
+	// bullets and mines touching the ship are used up
+	fn hostile_hit(&mut self, ship: &Vec<Point>) -> bool {
+		let mut hit = false;
+		let ships: Vec<Vec<Point>> = self.wrap_shifts(ship).iter().map(|d| shifted(ship, d)).collect();
+		for b in self.bullets.iter_mut() {
+			if ships.iter().any(|s| collide(s, &b.trail())) {
+				b.life = 0;
+				hit = true;
+			}
+		}
+		self.bullets.retain(|b| b.life > 0);
+		let mut blasts = vec!();
+		let mines: Vec<Vec<Point>> = self.mines.iter().map(|m| self.near_ship(&m.shape(), &m.pos)).collect();
+		for (m, shape) in self.mines.iter_mut().zip(mines.iter()) {
+			if m.alive && collide(ship, shape) {
+				m.alive = false;
+				blasts.push(m.pos.clone());
+			}
+		}
+		for pos in blasts.iter() {
+			for _ in 0..30 {
+				let dir = Point::new(self.rng.sign() * self.rng.nextfloat(), self.rng.sign() * self.rng.nextfloat());
+				self.particles.push(Particle::new("#ff5030", pos.clone(), dir));
+			}
+		}
+		hit || !blasts.is_empty()
+	}
+	fn apply_rotation(&mut self, amt: Deg) {
+		let mut drot: Deg = 0.0;
+		if self.lrot {
+			drot -= amt;
+		}
+		if self.rrot {
+			drot += amt;
+		}
+		self.rotate(drot);
+	}
+	fn apply_rcs(&mut self) {
+		let h = match &self.config.handling {
+			Some(h) => h.clone(),
+			None => return
+		};
+		let mut cmd = self.torque;
+		if self.lrot {
+			cmd -= 1.0;
+		}
+		if self.rrot {
+			cmd += 1.0;
+		}
+		let cmd = Fpt::max(-1.0, Fpt::min(1.0, cmd));
+		let cmd = if cmd == 0.0 && h.stabilize {
+			Fpt::max(-1.0, Fpt::min(1.0, -self.rot_vel / h.rcs_pow))
+		} else {
+			cmd
+		};
+
+		if cmd != 0.0 && self.monoprop > 0.0 && !self.blownup {
+			// one monoprop unit per step at full deflection
+			let burn = Fpt::min(cmd.abs(), self.monoprop);
+			self.monoprop -= burn;
+			self.rot_vel += burn * h.rcs_pow * cmd.signum() * self.mass_ratio();
+			if self.rng.nextfloat() <= burn {
+				self.rcs_puff(cmd > 0.0);
+			}
+		}
+		self.rot_vel *= 1.0 - h.damping;
+		self.rotate(self.rot_vel);
+	}
+	fn rcs_puff(&mut self, cw: bool) {
+		// turning clockwise fires the left nozzle of the nose
+		let side = if cw { -1.0 } else { 1.0 };
+		let pt = self.remap_ship(&Point::new(side * 5.0, -12.0));
+		let mut delta = Point::new(
+			side * 0.8 * self.trig.cos(self.rot),
+			side * 0.8 * self.trig.sin(self.rot)
+		);
+		delta.add(&self.speed);
+		self.particles.push(Particle::new("#ddd", pt, delta));
+	}
+	/* pub fn has_fuel(&self) -> bool {
+		self.fuel > 0
+	} */
+	pub fn has_collided(&self) -> bool {
+		self.collided
+	}
+	pub fn has_landed(&self) -> bool {
+		self.landed
+	}
+	fn collision(&self, ship: &[Point], resting: &[Contact]) -> bool {
+		for p in ship.iter() {
+			if !self.config.wrap && !inside_rect(&p, 0.0, 0.0, self.config.area.x(), self.config.area.y()) {
+				return true;
+			}
+		}
+		self.wrap_shifts(ship).iter().any(|d| self.collision_at(shifted(ship, d), resting))
+	}
+	fn collision_at(&self, ship: Vec<Point>, resting: &[Contact]) -> bool {
+		for (i, obs) in self.obs_shape().iter().enumerate() {
+			let hit = match resting.iter().find(|c| c.wall == Some(i)) {
+				Some(c) => collide(&self.clear_of(&ship, c), obs),
+				None => collide(&ship, &obs)
+			};
+			if hit {
+				return true;
+			}
+		}
+		for ast in self.asteroids.iter() {
+			if collide(&ship, &ast.remap4coll(&self.trig)) {
+				return true;
+			}
+		}
+		for (i, pad) in self.pads.iter().enumerate() {
+			let hit = match resting.iter().find(|c| c.pad == Some(i)) {
+				Some(c) => collide(&self.clear_of(&ship, c), pad),
+				None => collide(&ship, pad)
+			};
+			if hit {
+				return true;
+			}
+		}
+		self.config.lasers.iter().any(|l| self.laser_on(l) && collide(&ship, &l.beam()))
+	}
+	// a ship resting on a surface is checked just above it, its sides and
+	// underside still count
+	fn clear_of(&self, ship: &[Point], c: &Contact) -> Vec<Point> {
+		let mut out = self.trig.rot(&Point::new(0.0, -1.0), c.angle);
+		out.mul(c.depth + 1.0);
+		ship.iter().map(|p| {
+			let mut q = p.clone();
+			q.add(&out);
+			q
+		}).collect()
+	}
+	fn wrapped(&self, p: &Point) -> Point {
+		Point::new(p.x().rem_euclid(self.config.area.x()), p.y().rem_euclid(self.config.area.y()))
+	}
+	// a shape reaching past an edge of a wrapping level also sticks out of
+	// the opposite one: the offsets of those copies, none first
+	fn wrap_shifts(&self, shape: &[Point]) -> Vec<Point> {
+		let mut rv = vec![Point::new(0.0, 0.0)];
+		if !self.config.wrap || shape.is_empty() {
+			return rv;
+		}
+		let across = |lo: Fpt, hi: Fpt, size: Fpt| {
+			let mut d = vec![0.0];
+			if lo < 0.0 {
+				d.push(size);
+			}
+			if hi > size {
+				d.push(-size);
+			}
+			d
+		};
+		let xs = shape.iter().map(|p| p.x());
+		let ys = shape.iter().map(|p| p.y());
+		let dx = across(xs.clone().fold(Fpt::MAX, Fpt::min), xs.fold(Fpt::MIN, Fpt::max), self.config.area.x());
+		let dy = across(ys.clone().fold(Fpt::MAX, Fpt::min), ys.fold(Fpt::MIN, Fpt::max), self.config.area.y());
+		for x in dx.iter() {
+			for y in dy.iter() {
+				if *x != 0.0 || *y != 0.0 {
+					rv.push(Point::new(*x, *y));
+				}
+			}
+		}
+		rv
+	}
+	// moves a shape around p to the copy of p closest to the ship
+	fn near_ship(&self, shape: &[Point], p: &Point) -> Vec<Point> {
+		let near = self.offset(&self.pos, p);
+		shifted(shape, &Point::new(self.pos.x() + near.x() - p.x(), self.pos.y() + near.y() - p.y()))
+	}
+	// everything moving that can leave the area, shots wrap as they move
+	fn wrap_bodies(&mut self) {
+		self.pos = self.wrapped(&self.pos);
+		if let Some(pos) = self.cargo.as_ref().map(|c| self.wrapped(&c.pos)) {
+			if let Some(pod) = self.cargo.as_mut() {
+				pod.pos = pos;
+			}
+		}
+		let mines: Vec<Point> = self.mines.iter().map(|m| self.wrapped(&m.pos)).collect();
+		for (m, pos) in self.mines.iter_mut().zip(mines) {
+			m.pos = pos;
+		}
+	}
+	// shortest offset from a to b, across the edges when the level wraps
+	fn offset(&self, a: &Point, b: &Point) -> Point {
+		let (dx, dy) = (b.x() - a.x(), b.y() - a.y());
+		if !self.config.wrap {
+			return Point::new(dx, dy);
+		}
+		let (w, h) = (self.config.area.x(), self.config.area.y());
+		Point::new(dx - w * (dx / w).round(), dy - h * (dy / h).round())
+	}
+	fn use_portals(&mut self) {
+		if let Some((i, at_b)) = self.portal_lock {
+			let portal = &self.config.portals[i];
+			let (end, _) = if at_b { (&portal.b, &portal.a) } else { (&portal.a, &portal.b) };
+			if self.distance_to(end) < portal.radius {
+				return;
+			}
+			self.portal_lock = None;
+		}
+		let mut jump = None;
+		for (i, portal) in self.config.portals.iter().enumerate() {
+			for from_a in [true, false].iter() {
+				let (from, to, turn) = portal.route(*from_a);
+				if self.distance_to(from) < portal.radius {
+					jump = Some((i, *from_a, from.clone(), to.clone(), turn));
+				}
+			}
+		}
+		let (i, from_a, from, to, turn) = match jump {
+			Some(j) => j,
+			None => return
+		};
+		// keep the offset from the center, turned along with the speed
+		let carry = |p: &Point, trig: &Trig| {
+			let mut rel = trig.rot(&Point::new(p.x() - from.x(), p.y() - from.y()), turn);
+			rel.add(&to);
+			rel
+		};
+		self.pos = carry(&self.pos, &self.trig);
+		self.speed = self.trig.rot(&self.speed, turn);
+		self.rot = (self.rot + turn).rem_euclid(360.0);
+		if let Some(pod) = self.cargo.as_mut().filter(|c| c.towed) {
+			pod.pos = carry(&pod.pos, &self.trig);
+			pod.speed = self.trig.rot(&pod.speed, turn);
+		}
+		self.portal_lock = Some((i, from_a));
+		for (end, n) in [(from, 15), (to, 25)].iter() {
+			for _ in 0..*n {
+				let dir = Point::new(self.rng.sign() * self.rng.nextfloat(), self.rng.sign() * self.rng.nextfloat());
+				self.particles.push(Particle::new("#c080ff", end.clone(), dir));
+			}
+		}
+	}
+	fn signal(&self, id: u32) -> bool {
+		self.switches.iter().any(|s| s.id == id && s.on)
+	}
+	fn laser_on(&self, l: &LaserGate) -> bool {
+		l.cycle_on(self.step) && !l.id.is_some_and(|id| self.signal(id))
+	}
+	fn move_doors(&mut self) {
+		for s in self.switches.iter_mut() {
+			s.move_step();
+		}
+		for i in 0..self.walls.len() {
+			let (id, offset) = match self.config.door(i) {
+				Some(d) => d,
+				None => continue
+			};
+			let target = if self.signal(id) { 1.0 } else { 0.0 };
+			let open = self.doors[i] + Fpt::max(-DOOR_SPEED, Fpt::min(DOOR_SPEED, target - self.doors[i]));
+			self.doors[i] = open;
+			let shift = Point::new(offset.x() * open, offset.y() * open);
+			for p in self.walls[i].iter_mut() {
+				p.add(&shift);
+			}
+		}
+	}
+	fn trigger_switches(&mut self, ship: &Vec<Point>, contacts: &[Contact]) {
+		for s in self.switches.iter_mut() {
+			let hit = match &s.trigger {
+				Trigger::Zone(shape) => collide(ship, shape),
+				Trigger::Landing(wall) => contacts.iter().any(|c| c.wall == Some(*wall)),
+			};
+			if hit {
+				s.activate();
+			}
+		}
+	}
+	fn landing(&self) -> Option<usize> {
+		if self.blownup || !self.cargo_delivered() {
+			return None;
+		}
+		(0..self.pads.len()).find(|i| {
+			self.is_final_pad(*i) &&
+				self.level_on(self.pad_angle(*i), &self.pad_vels[*i]) &&
+				self.gear_contact(&self.pads[*i][0], &self.pads[*i][1]).is_some()
+		})
+	}
+
+	// no pod to carry counts as delivered
+	fn cargo_delivered(&self) -> bool {
+		self.cargo.as_ref().is_none_or(|c| c.is_delivered())
+	}
+	fn move_cargo(&mut self) {
+		let mut pod = match self.cargo.take() {
+			Some(c) => c,
+			None => return
+		};
+		if let Some((i, rest)) = &pod.rest {
+			let mut pos = self.trig.rot(rest, self.pad_angle(*i));
+			pos.add(&self.pads[*i][0]);
+			pod.pos = pos;
+			pod.speed = self.pad_vels[*i].clone();
+		} else if !pod.towed {
+			// hover close to the pod for a while to hook it
+			if self.distance_to(&pod.pos) < Cargo::REACH {
+				pod.latch += 1;
+				pod.towed = pod.latch >= Cargo::LATCH_STEPS;
+			} else {
+				pod.latch = 0;
+			}
+		} else if !pod.lifted {
+			let anchor = self.remap_ship(&Point::new(0.0, 10.0));
+			pod.lifted = self.offset(&anchor, &pod.pos).length() > Cargo::ROPE;
+		}
+		if pod.lifted && pod.rest.is_none() {
+			pod.speed.add(&self.gravity_at(&pod.pos));
+			let shape = pod.shape();
+			for wind in self.winds.iter() {
+				if collide(&shape, wind.shape()) {
+					pod.speed.add(wind.accel());
+				}
+			}
+			pod.speed.mul(self.config.friction);
+			pod.pos.add(&pod.speed);
+			self.pull_rope(&mut pod);
+			self.land_cargo(&mut pod);
+			if !pod.is_delivered() && self.cargo_collision(&pod.shape()) {
+				self.collided = true;
+				self.blownup = true;
+				self.blowup();
+				for _ in 0..60 {
+					let dir = Point::new(self.rng.sign() * self.rng.nextfloat(), self.rng.sign() * self.rng.nextfloat());
+					self.particles.push(Particle::new("#d9a441", pod.pos.clone(), dir));
+				}
+			}
+		}
+		self.cargo = Some(pod);
+	}
+	// the rope only pulls when taut, the lighter end moves the most
+	fn pull_rope(&mut self, pod: &mut Cargo) {
+		let anchor = self.remap_ship(&Point::new(0.0, 10.0));
+		let mut d = self.offset(&anchor, &pod.pos);
+		let len = d.length();
+		if len <= Cargo::ROPE {
+			return;
+		}
+		d.mul(1.0 / len);
+		let ship_mass = self.mass();
+		let ship_share = Cargo::MASS / (ship_mass + Cargo::MASS);
+		let pod_share = ship_mass / (ship_mass + Cargo::MASS);
+
+		let excess = len - Cargo::ROPE;
+		let mut dp = d.clone();
+		dp.mul(excess * ship_share);
+		self.pos.add(&dp);
+		let mut dp = d.clone();
+		dp.mul(-excess * pod_share);
+		pod.pos.add(&dp);
+
+		let vrel = (pod.speed.x() - self.speed.x()) * d.x() + (pod.speed.y() - self.speed.y()) * d.y();
+		if vrel > 0.0 {
+			let mut dv = d.clone();
+			dv.mul(vrel * ship_share);
+			self.speed.add(&dv);
+			let mut dv = d.clone();
+			dv.mul(-vrel * pod_share);
+			pod.speed.add(&dv);
+		}
+	}
+	// the pod is delivered when it gently touches a pad the game can end on
+	fn land_cargo(&mut self, pod: &mut Cargo) {
+		for i in 0..self.pads.len() {
+			if !self.is_final_pad(i) {
+				continue;
+			}
+			let (a, b) = (&self.pads[i][0], &self.pads[i][1]);
+			// the copy of the pod closest to the pad
+			let mut pos = self.offset(a, &pod.pos);
+			pos.add(a);
+			let bottom = Point::new(pos.x(), pos.y() + Cargo::SIZE);
+			let g = self.surface_local(a, b, &bottom);
+			if g.x() < 0.0 || g.x() > Self::surface_length(a, b) || g.y() < 0.0 || g.y() > 5.0 {
+				continue;
+			}
+			let rel = Point::new(pod.speed.x() - self.pad_vels[i].x(), pod.speed.y() - self.pad_vels[i].y());
+			if rel.x().abs() < self.config.levelling_speed_x && rel.y().abs() < self.config.levelling_speed_y {
+				let center = self.surface_local(a, b, &pos);
+				pod.rest = Some((i, Point::new(center.x(), -Cargo::SIZE)));
+				pod.towed = false;
+				pod.lifted = false;
+				self.bonus += CARGO_SCORE;
+				return;
+			}
+		}
+	}
+	fn cargo_collision(&self, pod: &[Point]) -> bool {
+		for p in pod.iter() {
+			if !self.config.wrap && !inside_rect(p, 0.0, 0.0, self.config.area.x(), self.config.area.y()) {
+				return true;
+			}
+		}
+		self.wrap_shifts(pod).iter().any(|d| self.cargo_collision_at(shifted(pod, d)))
+	}
+	fn cargo_collision_at(&self, pod: Vec<Point>) -> bool {
+		self.config.lasers.iter().any(|l| self.laser_on(l) && collide(&pod, &l.beam())) ||
+			self.obs_shape().iter().any(|obs| collide(&pod, obs)) ||
+			self.asteroids.iter().any(|ast| collide(&pod, &ast.remap4coll(&self.trig))) ||
+			self.pads.iter().any(|pad| collide(&pod, pad))
+	}
+
+	fn collide_asteroids(&mut self) {
+		let shapes: Vec<Vec<Point>> = self.asteroids.iter().map(|a| a.remap4coll(&self.trig)).collect();
+		for i in 0..self.asteroids.len() {
+			for j in i+1..self.asteroids.len() {
+				let (head, tail) = self.asteroids.split_at_mut(j);
+				let (a, b) = (&mut head[i], &mut tail[0]);
+				let mut n = b.pos.clone();
+				n.add(&Point::new(-a.pos.x(), -a.pos.y()));
+				let dist = n.length();
+				if dist == 0.0 || dist > a.radius + b.radius || !collide(&shapes[i], &shapes[j]) {
+					continue;
+				}
+				n.mul(1.0 / dist);
+				// touching along the line between the centers
+				let da = dist * a.radius / (a.radius + b.radius);
+				let ba = a.body(Point::new(n.x() * da, n.y() * da));
+				let bb = b.body(Point::new(-n.x() * (dist - da), -n.y() * (dist - da)));
+				let mut rel = b.velocity_at(&bb.arm);
+				let va = a.velocity_at(&ba.arm);
+				rel.add(&Point::new(-va.x(), -va.y()));
+				if let Some(imp) = contact_impulse(&rel, &n, &ba, &bb) {
+					b.push(&imp, &bb);
+					a.push(&Point::new(-imp.x(), -imp.y()), &ba);
+				}
+				// keep them from sinking into each other
+				a.pos.add(&Point::new(-n.x() * 0.5, -n.y() * 0.5));
+				b.pos.add(&Point::new(n.x() * 0.5, n.y() * 0.5));
+			}
+		}
+
+		for (k, ast) in self.asteroids.iter_mut().enumerate() {
+			let obstacles = self.walls.iter().zip(self.prev_walls.iter()).map(|(w, prev)| (w, Some(prev), None))
+				.chain(self.pads.iter().zip(self.pad_vels.iter()).map(|(p, vel)| (p, None, Some(vel))));
+			for (shape, prev, vel) in obstacles {
+				if shape.len() < 2 {
+					continue;
+				}
+				let (edge, t, q) = match nearest_edge(shape, &ast.pos) {
+					Some(e) => e,
+					None => continue
+				};
+				let mut n = ast.pos.clone();
+				n.add(&Point::new(-q.x(), -q.y()));
+				let dist = n.length();
+				if dist == 0.0 || (dist > ast.radius && !inside_shape(shape, &ast.pos)) || !collide(&shapes[k], shape) {
+					continue;
+				}
+				// out through the closest edge when the center got inside
+				n.mul(if inside_shape(shape, &ast.pos) { -1.0 / dist } else { 1.0 / dist });
+				// velocity of the touched point of the wall
+				let wall_vel = match (prev, vel) {
+					(Some(prev), _) if prev.len() == shape.len() => {
+						let a = &prev[edge];
+						let b = &prev[(edge + 1) % prev.len()];
+						Point::new(q.x() - a.x() - t * (b.x() - a.x()), q.y() - a.y() - t * (b.y() - a.y()))
+					},
+					(_, Some(v)) => v.clone(),
+					_ => Point::new(0.0, 0.0)
+				};
+				let body = ast.body(Point::new(q.x() - ast.pos.x(), q.y() - ast.pos.y()));
+				let mut rel = ast.velocity_at(&body.arm);
+				rel.add(&Point::new(-wall_vel.x(), -wall_vel.y()));
+				if let Some(imp) = contact_impulse(&rel, &n, &ContactBody::wall(), &body) {
+					ast.push(&imp, &body);
+				}
+				ast.pos.add(&n);
+			}
+		}
+	}
+
+	fn blowup(&mut self) {
+		let base = self.remap_ship(&Point::new(0.0,0.0));
+		for _ in 0..200 {
+			let x = base.x() - 15.0 + (self.rng.nextbits(5) as Fpt);
+			let y = base.y() - 15.0 + (self.rng.nextbits(5) as Fpt);
+			let dx = (x-base.x()) / ((self.rng.nextbits(3) + 5) as Fpt) + self.speed.x();
+			let dy = (y-base.y()) / ((self.rng.nextbits(3) + 5) as Fpt) + self.speed.y();
+			self.particles.push(Particle::new("#42a4f5", Point::new(x,y), Point::new(dx,dy)));
+		}
+	}
+
+	pub fn move_step(&mut self) {
 		self.step = self.step + 1;
-		self.walls = self.config.get_walls(self.step, &self.trig);
+		self.prev_walls = std::mem::replace(&mut self.walls, self.config.get_walls(self.step, &self.trig));
+		self.move_doors();
+		let pads = self.config.get_pads(self.step, &self.trig);
+		self.pad_vels = self.pads.iter().zip(pads.iter()).map(|(a, b)| Self::edge_vel(a, b)).collect();
+		self.pads = pads;
+		self.mounts = self.config.get_turrets(self.step, &self.trig);
+		for w in self.winds.iter_mut() {
+			w.move_step(self.step, &self.trig);
+		}
 
 		for a in self.asteroids.iter_mut() {
-			a.move_step(&self.config.area);
+			a.move_step(&self.config.area, self.config.wrap);
 		}
 		self.asteroids.retain(|p| { !p.finished() });
+		self.collide_asteroids();
+		self.move_projectiles();
+		self.move_hostiles();
 
 		if !self.blownup {
 			self.pos.add(&self.speed);
-			self.landed = self.landed || self.landing();
-			if self.landed {
+			if self.landed_pad.is_none() {
+				self.landed_pad = self.landing();
+				if let Some(i) = self.landed_pad {
+					self.landed = true;
+					self.score = Some(((LANDING_SCORE + self.fuel_sz(LANDING_SCORE)) * self.config.pads[i].multiplier()).round() as u32 + self.bonus);
+				}
+			}
+			if let Some(i) = self.landed_pad {
 				self.collided = false;
-				self.rot = 0;
-				self.speed = Point::new(0.0,0.0);
+				self.rot_vel = 0.0;
+				// the ship rides the pad, upright relative to it
+				let angle = self.pad_angle(i);
+				let rest = match self.pad_rest.take() {
+					Some(p) => p,
+					None => self.surface_local(&self.pads[i][0], &self.pads[i][1], &self.pos)
+				};
+				let mut pos = self.trig.rot(&rest, angle);
+				pos.add(&self.pads[i][0]);
+				self.pos = pos;
+				self.pad_rest = Some(rest);
+				self.rot = angle.rem_euclid(360.0);
+				self.speed = self.pad_vels[i].clone();
 			} else {
 				let ship = self.ship_shape();
-				self.collided = self.collision(&ship);
+				let resting = self.rest_contacts();
+				self.collided = self.collision(&ship, &resting);
+				self.collided = self.hostile_hit(&ship) || self.collided;
+				if self.collided && (self.shield > 0 || self.grace > 0) {
+					self.absorb_collision(&resting);
+					self.collided = false;
+				}
 				if self.collided {
 					self.blownup = true;
 					self.blowup();
 				}
 				self.apply_gravity();
 				self.apply_wind(&ship);
-				self.apply_rotation(6);
+				self.apply_fluids(&ship);
+				if self.has_inertia() {
+					self.apply_rcs();
+				} else {
+					self.apply_rotation(6.0);
+				}
 				self.apply_thrust(self.config.thrust_pow);
+				self.fire();
 				self.friction(self.config.friction);
+				if !self.collided {
+					self.move_cargo();
+				}
+				if !self.collided {
+					self.trigger_switches(&ship, &resting);
+					self.use_portals();
+					self.collect_pickups(&ship);
+					self.apply_platforms(&resting);
+					self.refuel(&resting);
+					self.reach_stage(&resting);
+				}
 			}
 		}
 
@@ -359,35 +2050,242 @@ impl GameEngine {
 			p.move_step();
 		}
 		self.particles.retain(|p| { !p.finished() });
+		for p in self.pickups.iter_mut() {
+			p.move_step();
+		}
+		self.shield = self.shield.saturating_sub(1);
+		self.grace = self.grace.saturating_sub(1);
+		if self.blownup && self.particles.is_empty() {
+			self.respawn();
+		}
+		if self.is_mission() && !self.landed {
+			self.mission_steps += 1;
+		}
+		if self.config.wrap {
+			self.wrap_bodies();
+		}
 		self.reposition_viewport();
 	}
 
+	// ship-only flight with no pilot input: gravity, winds, fluids and
+	// friction, against the walls and pads where they are now
+	pub fn predict(&self, steps: u32) -> Trajectory {
+		let mut rv = Trajectory {
+			path: Vec::with_capacity(steps as usize),
+			impact: None,
+			landed: false,
+		};
+		if self.blownup || self.landed {
+			return rv;
+		}
+
+		let ratio = self.mass_ratio();
+		let mut winds = self.winds.clone();
+		let mut pos = self.pos.clone();
+		let mut speed = self.speed.clone();
+		for k in 1..=steps {
+			pos.add(&speed);
+			if self.config.wrap {
+				pos = self.wrapped(&pos);
+			}
+			let ship = self.ship_at(&pos);
+			let rests = self.rests_at(&pos, &speed);
+			if rests || self.hits_obstacle(&ship) {
+				rv.impact = Some(pos);
+				rv.landed = rests;
+				break;
+			}
+			let gravity = self.gravity_at(&pos);
+			speed.add(&gravity);
+			for w in winds.iter_mut() {
+				w.move_step(self.step + k, &self.trig);
+				if collide(&ship, w.shape()) {
+					let mut acc = w.accel().clone();
+					acc.mul(ratio);
+					speed.add(&acc);
+				}
+			}
+			for fluid in self.config.fluids.iter() {
+				if collide(&ship, fluid.shape()) {
+					let mut lift = gravity.clone();
+					lift.mul(-fluid.buoyancy * ratio);
+					speed.add(&lift);
+					speed.mul(fluid.drag);
+				}
+			}
+			speed.mul(self.config.friction);
+			rv.path.push(pos.clone());
+		}
+		rv
+	}
+	// the gear gently touches a pad or a platform
+	fn rests_at(&self, pos: &Point, speed: &Point) -> bool {
+		let pads = self.pads.iter().zip(self.pad_vels.iter()).map(|(s, v)| (s, v.clone()));
+		let platforms = self.walls.iter().enumerate()
+			.filter(|(i, s)| self.config.is_platform(*i) && s.len() >= 2)
+			.map(|(i, s)| (s, Self::edge_vel(&self.prev_walls[i], s)));
+		pads.chain(platforms).any(|(s, vel)| {
+			self.level_at(speed, Self::surface_angle(&s[0], &s[1]), &vel) &&
+				self.gear_contact_at(pos, &s[0], &s[1]).is_some()
+		})
+	}
+	fn hits_obstacle(&self, ship: &Vec<Point>) -> bool {
+		(!self.config.wrap && ship.iter().any(|p| !inside_rect(p, 0.0, 0.0, self.config.area.x(), self.config.area.y()))) ||
+			self.walls.iter().chain(self.pads.iter()).any(|w| collide(ship, w))
+	}
+
 	pub fn remap_ship(&self, p: &Point) -> Point {
+		self.remap_at(&self.pos, p)
+	}
+	fn remap_at(&self, pos: &Point, p: &Point) -> Point {
 		let mut tr = self.trig.rot(p, self.rot);
-		tr.add(&self.pos);
+		tr.add(pos);
 		tr
 	}
 
 	pub fn fuel_sz(&self, maxref: Fpt) -> Fpt {
-		(self.fuel as Fpt) * maxref / self.config.full_fuel
+		self.fuel * maxref / self.config.full_fuel
+	}
+	pub fn rcs_sz(&self, maxref: Fpt) -> Fpt {
+		match &self.config.handling {
+			Some(h) => self.monoprop * maxref / h.monoprop,
+			None => 0.0
+		}
+	}
+	// degrees per step, positive clockwise
+	pub fn rot_speed(&self) -> Fpt {
+		self.rot_vel
+	}
+	pub fn ship_speed(&self) -> Point {
+		self.speed.clone()
+	}
+	// attitude in degrees, 0 is upright, negative when tilted left
+	pub fn attitude(&self) -> Deg {
+		if self.rot > 180.0 { self.rot - 360.0 } else { self.rot }
+	}
+	// height of the landing gear above the pad surface
+	pub fn pad_altitude(&self) -> Fpt {
+		self.pad_center().y() - self.remap_ship(&Point::new(0.0, 10.0)).y()
+	}
+	pub fn pad_center(&self) -> Point {
+		self.pad_center_of(self.target_pad())
+	}
+	pub fn pad_center_of(&self, i: usize) -> Point {
+		Point::new(
+			(self.pads[i][0].x() + self.pads[i][1].x()) / 2.0,
+			(self.pads[i][0].y() + self.pads[i][1].y()) / 2.0
+		)
+	}
+	fn distance_to(&self, p: &Point) -> Fpt {
+		let mut d = p.clone();
+		d.mul(-1.0);
+		d.add(&self.remap_ship(&Point::new(0.0, 10.0)));
+		d.length()
+	}
+	pub fn score(&self) -> Option<u32> {
+		self.score
+	}
+	pub fn pad_distance(&self) -> Fpt {
+		self.distance_to(&self.pad_center())
+	}
+	pub fn levelling_speed_x(&self) -> Fpt {
+		self.config.levelling_speed_x
+	}
+	pub fn levelling_speed_y(&self) -> Fpt {
+		self.config.levelling_speed_y
+	}
+	pub fn levelling_rot(&self) -> i32 {
+		self.config.levelling_rot
 	}
 	pub fn ship_shape(&self) -> Vec<Point> {
 		if self.blownup {
 			return vec![];
 		}
+		self.ship_at(&self.pos)
+	}
+	fn ship_at(&self, pos: &Point) -> Vec<Point> {
 		vec![
 			Point::new(0.0, -20.0),
 			Point::new(-10.0, 10.0),
 			Point::new(10.0, 10.0),
-		].iter().map(|p| self.remap_ship(p)).collect()
-	}
-	pub fn land_shape(&self) -> Vec<Point> {
-		vec!(
-			Point::new(self.config.target_x0, self.config.target_y),
-			Point::new(self.config.target_x1, self.config.target_y),
-			Point::new(self.config.target_x1, self.config.target_y + 5.0),
-			Point::new(self.config.target_x0, self.config.target_y + 5.0)
-		)
+		].iter().map(|p| self.remap_at(pos, p)).collect()
+	}
+	pub fn iter_pads<F>(&self, mut f: F) where F: FnMut(&Vec<Point>, &Pad, Fpt, Option<usize>) {
+		for (i, shape) in self.pads.iter().enumerate() {
+			f(shape, &self.config.pads[i], self.pad_fuel[i], self.config.stages.iter().position(|s| *s == i));
+		}
+	}
+	pub fn cargo(&self) -> Option<&Cargo> {
+		self.cargo.as_ref()
+	}
+	// from the rope anchor under the ship to the pod
+	pub fn rope(&self) -> Option<Point> {
+		let pod = self.cargo.as_ref()?;
+		Some(self.offset(&self.remap_ship(&Point::new(0.0, 10.0)), &pod.pos))
+	}
+	pub fn iter_projectiles<F>(&self, mut f: F) where F: FnMut(&Projectile) {
+		for p in self.projectiles.iter() {
+			f(p);
+		}
+	}
+	// turret, where it is and where its barrel points
+	pub fn iter_turrets<F>(&self, mut f: F) where F: FnMut(&Turret, &Point, Deg) {
+		for (t, (pos, facing)) in self.turrets.iter().zip(self.mounts.iter()) {
+			f(t, pos, facing + t.aim());
+		}
+	}
+	pub fn iter_portals<F>(&self, mut f: F) where F: FnMut(usize, &Portal) {
+		for (i, p) in self.config.portals.iter().enumerate() {
+			f(i, p);
+		}
+	}
+	pub fn wraps(&self) -> bool {
+		self.config.wrap
+	}
+	pub fn iter_switches<F>(&self, mut f: F) where F: FnMut(&Switch) {
+		for s in self.switches.iter() {
+			f(s);
+		}
+	}
+	pub fn iter_lasers<F>(&self, mut f: F) where F: FnMut(&LaserGate, bool) {
+		for l in self.config.lasers.iter() {
+			f(l, self.laser_on(l));
+		}
+	}
+	pub fn iter_mines<F>(&self, mut f: F) where F: FnMut(&Mine) {
+		for m in self.mines.iter() {
+			f(m);
+		}
+	}
+	pub fn iter_bullets<F>(&self, mut f: F) where F: FnMut(&Projectile) {
+		for b in self.bullets.iter() {
+			f(b);
+		}
+	}
+	pub fn iter_pickups<F>(&self, mut f: F) where F: FnMut(&Pickup) {
+		for p in self.pickups.iter() {
+			f(p);
+		}
+	}
+	// steps of shield left
+	pub fn shield(&self) -> u32 {
+		self.shield
+	}
+	pub fn bonus(&self) -> u32 {
+		self.bonus
+	}
+	pub fn is_mission(&self) -> bool {
+		!self.config.stages.is_empty()
+	}
+	// stages completed so far
+	pub fn stage(&self) -> usize {
+		self.stage
+	}
+	pub fn stage_count(&self) -> usize {
+		self.config.stages.len()
+	}
+	pub fn mission_steps(&self) -> u32 {
+		self.mission_steps
 	}
 	pub fn aster_shape(&self) -> Vec<Vec<Point>> {
 		let mut rv = Vec::with_capacity(self.asteroids.len());
@@ -401,28 +2299,38 @@ impl GameEngine {
 	}
 
 	pub fn iter_winds<F>(&self, mut f: F) where F: FnMut(&Wind, &Trig) -> () {
-		for w in self.config.winds.iter() {
+		for w in self.winds.iter() {
 			f(&w, &self.trig);
 		}
 	}
 
+	pub fn iter_wells<F>(&self, mut f: F) where F: FnMut(&GravityWell) {
+		for w in self.config.gravity_wells.iter() {
+			f(w);
+		}
+	}
+
 	pub fn iter_part<F>(&self, mut f: F) where F: FnMut(&Particle) -> () {
 		for p in self.particles.iter() {
 			f(&p);
 		}
 	}
 
-	fn new_asteroid(&mut self) -> Asteroid {
+	fn asteroid_vertices(&mut self, size: Fpt) -> Vec<Point> {
 		let mut v = vec![];
 		let mut vgen = self.rng.nextbits(6) as i32;
 		while vgen < 360 {
-			let vdist = (self.rng.nextbits(5) + 30) as Fpt;
+			let vdist = (self.rng.nextbits(5) + 30) as Fpt * size;
 			v.push(Point::new(
-				vdist * self.trig.sin(vgen),
-				vdist * self.trig.cos(vgen)
+				vdist * self.trig.sin(vgen as Deg),
+				vdist * self.trig.cos(vgen as Deg)
 			));
 			vgen += self.rng.nextbits(5) as i32 + 30;
 		}
+		v
+	}
+	fn new_asteroid(&mut self) -> Asteroid {
+		let v = self.asteroid_vertices(1.0);
 		let p = Point::new(
 			self.config.asteroid_pos0.x() + self.rng.rand(self.config.asteroid_area.x() as i32) as Fpt,
 			self.config.asteroid_pos0.y() + self.rng.rand(self.config.asteroid_area.y() as i32) as Fpt
@@ -431,20 +2339,24 @@ impl GameEngine {
 			self.rng.sign() * (self.rng.nextfloat() + 0.2),
 			self.rng.sign() * (self.rng.nextfloat() + 0.2)
 		);
-		let dr = self.rng.nextbits(3) as i32 - 4;
+		// -4 to +3.75 degrees per step
+		let dr = (self.rng.nextbits(5) as Deg - 16.0) / 4.0;
+		let radius = v.iter().map(|p| p.length()).fold(0.0, Fpt::max);
 		Asteroid {
 			vert: v,
 			pos: p,
-			rot: 0,
+			rot: 0.0,
 			dpos: dp,
 			drot: dr,
+			radius,
+			size: 1.0,
 		}
 	}
 	pub fn finished(&self) -> bool {
-		(self.blownup && (self.particles.len() == 0)) || self.stuck() || self.landed
+		(self.blownup && self.particles.is_empty() && self.checkpoint.is_none()) || self.stuck() || self.landed
 	}
 	fn stuck(&self) -> bool {
-		!self.landed && !self.blownup && self.fuel == 0u32 && self.speed.is_zero() && self.config.gravity.is_zero()
+		!self.landed && !self.blownup && self.fuel <= 0.0 && self.speed.is_zero() && self.gravity_at(&self.pos).is_zero()
 	}
 	pub fn area_width(&self) -> Fpt {
 		self.config.area.x()
@@ -459,28 +2371,10 @@ impl GameEngine {
 		}
 	}
 	fn reposition_viewport(&mut self) {
-		if ! self.scrollable() {
-			return;
+		let pad = self.pad_center();
+		if let Some(cam) = self.camera.as_mut() {
+			cam.update(&self.pos, &self.speed, &pad);
 		}
-		let margin = 150.0;
-
-		let aw = self.config.area.x();
-		let ah = self.config.area.y();
-		let vpw = match &self.config.viewport { Some(v) => v.x(), None => return };
-		let vph = match &self.config.viewport { Some(v) => v.y(), None => return };
-		let vpx = match &self.viewport_pos { Some(v) => v.x(), None => 0.0 };
-		let vpy = match &self.viewport_pos { Some(v) => v.y(), None => 0.0 };
-
-		let vpx = f64::min(vpx, self.pos.x() - margin);
-		let vpx = f64::max(vpx + vpw, self.pos.x() + margin) - vpw;
-		let vpx = f64::max(vpx, -30.0);
-		let vpx = f64::min(vpx, aw - vpw + 30.0);
-
-		let vpy = f64::min(vpy, self.pos.y() - margin);
-		let vpy = f64::max(vpy + vph, self.pos.y() + margin) - vph;
-		let vpy = f64::max(vpy, -30.0);
-		let vpy = f64::min(vpy, ah - vph + 30.0);
-		self.viewport_pos = Some(Point::new(vpx, vpy));
 	}
 
 	pub fn viewport_width(&self) -> Fpt {
@@ -496,11 +2390,24 @@ impl GameEngine {
 		}
 	}
 	pub fn viewport_pos(&self) -> Point {
-		match &self.viewport_pos {
-			Some(v) => v.clone(),
+		match &self.camera {
+			Some(c) => c.origin(),
 			None => Point::new(0.0, 0.0)
 		}
 	}
+	pub fn viewport_zoom(&self) -> Fpt {
+		match &self.camera {
+			Some(c) => c.zoom(),
+			None => 1.0
+		}
+	}
+	// world size covered by the viewport at the current zoom
+	pub fn visible_width(&self) -> Fpt {
+		self.viewport_width() / self.viewport_zoom()
+	}
+	pub fn visible_height(&self) -> Fpt {
+		self.viewport_height() / self.viewport_zoom()
+	}
 
 	pub fn block_alert(&self) -> bool {
 		self.block_alert
@@ -516,3 +2423,434 @@ impl GameEngine {
 	} */
 }
 
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// ship hovering level just over the middle of pad i, sinking slowly
+	fn hover_over(engine: &mut GameEngine, i: usize) {
+		let mut pos = engine.pad_center_of(i);
+		pos.add(&Point::new(0.0, -12.0));
+		engine.pos = pos;
+		engine.speed = Point::new(0.0, 0.5);
+		engine.rot = 0.0;
+	}
+
+	fn settle(engine: &mut GameEngine) {
+		for _ in 0..20 {
+			engine.move_step();
+		}
+	}
+
+	#[test]
+	fn prediction_follows_the_flight_without_input() {
+		let mut engine = GameEngine::new(GameData::shifted(0, 1, 1, 2, 1, 0));
+		for _ in 0..40 {
+			engine.move_step();
+		}
+		let traj = engine.predict(60);
+		assert_eq!(traj.path().len(), 60);
+		for p in traj.path().iter() {
+			engine.move_step();
+			let pos = engine.ship_pos();
+			assert!((p.x() - pos.x()).abs() < 1e-9 && (p.y() - pos.y()).abs() < 1e-9);
+		}
+	}
+
+	#[test]
+	fn resting_ship_still_hits_the_platform_underside() {
+		let mut engine = GameEngine::new(GameData::vault(0, 1, 2, 0, 1, 0));
+		let i = (0..engine.walls.len()).find(|i| engine.config.is_platform(*i)).unwrap();
+		// gear on the top edge of the block, from 230,495 to 310,495
+		engine.pos = Point::new(270.0, 485.0);
+		engine.speed = Point::new(0.0, 0.0);
+		let resting = engine.rest_contacts();
+		assert!(resting.iter().any(|c| c.wall == Some(i)));
+		assert!(!engine.collision(&engine.ship_shape(), &resting));
+
+		// nose poking into the block from below
+		engine.pos = Point::new(270.0, 520.0);
+		assert!(engine.collision(&engine.ship_shape(), &resting));
+	}
+
+	#[test]
+	fn stage_pad_refuels_from_its_stock() {
+		let mut engine = GameEngine::new(GameData::relay(0, 1, 0, 0, 1, 0));
+		let fuel = engine.fuel;
+		hover_over(&mut engine, 0);
+		settle(&mut engine);
+		assert_eq!(engine.stage(), 1);
+		assert!(engine.fuel > fuel);
+		assert!(engine.fuel <= fuel + 20.0 * REFUEL_RATE);
+		assert!(engine.fuel < engine.config.full_fuel);
+		assert!(engine.mission_steps() > 0);
+	}
+
+	#[test]
+	fn mission_timer_only_runs_in_missions() {
+		let mut engine = GameEngine::new(GameData::simple(0, 1, 2, 0, 1, 0));
+		settle(&mut engine);
+		assert_eq!(engine.mission_steps(), 0);
+	}
+
+	#[test]
+	fn shield_absorbs_one_hit() {
+		let mut engine = GameEngine::new(GameData::simple(0, 1, 2, 0, 1, 0));
+		// too fast to land on the pad, 100,600 to 220,600
+		engine.pos = Point::new(160.0, 560.0);
+		engine.speed = Point::new(0.0, 4.0);
+		engine.shield = 100;
+		for _ in 0..60 {
+			engine.move_step();
+			assert!(!engine.has_collided());
+		}
+		assert_eq!(engine.shield(), 0);
+		assert!(engine.ship_speed().y() < 0.0);
+
+		engine.pos = Point::new(160.0, 560.0);
+		engine.speed = Point::new(0.0, 4.0);
+		settle(&mut engine);
+		assert!(engine.has_collided());
+	}
+
+	#[test]
+	fn shield_survives_a_wall_moving_into_the_ship() {
+		let mut engine = GameEngine::new(GameData::ferry(0, 1, 2, 0, 1, 0));
+		for _ in 0..80 {
+			engine.move_step();
+		}
+		// tilted over the elevator as it comes up
+		let top = engine.walls[1][0].y();
+		engine.pos = Point::new(310.0, top - 25.0);
+		engine.speed = Point::new(0.0, 0.0);
+		engine.rot = 45.0;
+		engine.shield = 100;
+		for _ in 0..40 {
+			engine.move_step();
+			assert!(!engine.has_collided());
+		}
+		assert_eq!(engine.shield(), 0);
+	}
+
+	#[test]
+	fn cargo_is_latched_lifted_and_delivered() {
+		let mut engine = GameEngine::new(GameData::delivery(0, 1, 2, 0, 1, 0));
+		// gear on the pad, 20,600 to 160,600
+		let on_pad = Point::new(90.0, 590.0);
+		engine.pos = on_pad.clone();
+		assert!(engine.landing().is_none());
+
+		let pod = engine.cargo().unwrap().pos.clone();
+		for _ in 0..Cargo::LATCH_STEPS {
+			engine.pos = Point::new(pod.x(), pod.y() - 40.0);
+			engine.speed = Point::new(0.0, 0.0);
+			engine.move_step();
+		}
+		assert!(engine.cargo().unwrap().is_towed());
+		assert!(!engine.cargo().unwrap().lifted);
+
+		for i in 0..20 {
+			engine.pos = Point::new(pod.x(), pod.y() - 41.0 - i as Fpt);
+			engine.speed = Point::new(0.0, 0.0);
+			engine.move_step();
+		}
+		assert!(engine.cargo().unwrap().lifted);
+		assert!(engine.cargo().unwrap().pos.y() < pod.y());
+
+		// hanging just over the pad, slowly coming down
+		engine.pos = Point::new(90.0, 560.0);
+		if let Some(c) = engine.cargo.as_mut() {
+			c.pos = Point::new(90.0, 594.0);
+			c.speed = Point::new(0.0, 0.5);
+		}
+		engine.move_step();
+		assert!(engine.cargo().unwrap().is_delivered());
+		assert_eq!(engine.bonus(), CARGO_SCORE);
+
+		engine.pos = on_pad;
+		engine.speed = Point::new(0.0, 0.0);
+		assert_eq!(engine.landing(), Some(0));
+	}
+
+	#[test]
+	fn asteroids_bounce_apart_keeping_momentum() {
+		let mut engine = GameEngine::new(GameData::simple(0, 1, 2, 0, 1, 0));
+		let vert = engine.asteroid_vertices(1.0);
+		let radius = vert.iter().map(|p| p.length()).fold(0.0, Fpt::max);
+		for (x, vx) in [(100.0, 1.0), (100.0 + radius, -1.0)].iter() {
+			engine.asteroids.push(Asteroid {
+				vert: vert.clone(),
+				pos: Point::new(*x, 300.0),
+				rot: 0.0,
+				dpos: Point::new(*vx, 0.0),
+				drot: 0.0,
+				radius,
+				size: 1.0,
+			});
+		}
+		let momentum = |e: &GameEngine| e.asteroids.iter().fold(Point::new(0.0, 0.0), |mut p, a| {
+			p.add(&Point::new(a.dpos.x() * a.radius * a.radius, a.dpos.y() * a.radius * a.radius));
+			p
+		});
+		let before = momentum(&engine);
+		engine.collide_asteroids();
+		let after = momentum(&engine);
+		assert!((after.x() - before.x()).abs() < 1e-6 && (after.y() - before.y()).abs() < 1e-6);
+		let (a, b) = (&engine.asteroids[0], &engine.asteroids[1]);
+		assert!(b.dpos.x() - a.dpos.x() > 0.0);
+
+		let gap = b.pos.x() - a.pos.x();
+		for _ in 0..20 {
+			for ast in engine.asteroids.iter_mut() {
+				ast.move_step(&engine.config.area, false);
+			}
+		}
+		assert!(engine.asteroids[1].pos.x() - engine.asteroids[0].pos.x() > gap);
+	}
+
+	#[test]
+	fn firing_uses_ammo_and_waits_for_the_cooldown() {
+		let mut engine = GameEngine::new(GameData::simple(0, 1, 2, 0, 1, 0));
+		engine.ammo = 2;
+		engine.set_firing(true);
+		engine.fire();
+		assert_eq!((engine.ammo(), engine.projectiles.len()), (1, 1));
+		for _ in 1..Projectile::COOLDOWN {
+			engine.fire();
+		}
+		assert_eq!((engine.ammo(), engine.projectiles.len()), (1, 1));
+		engine.fire();
+		assert_eq!((engine.ammo(), engine.projectiles.len()), (0, 2));
+		for _ in 0..2 * Projectile::COOLDOWN {
+			engine.fire();
+		}
+		assert_eq!(engine.projectiles.len(), 2);
+	}
+
+	#[test]
+	fn a_shot_takes_out_one_mine() {
+		let mut engine = GameEngine::new(GameData::simple(0, 1, 2, 0, 1, 0));
+		let pos = Point::new(engine.area_width() / 2.0, 150.0);
+		engine.mines = vec![Mine::new(pos.clone()), Mine::new(pos.clone())];
+		engine.projectiles.push(Projectile {
+			pos: Point::new(pos.x() - 3.0, pos.y()),
+			speed: Point::new(Projectile::SPEED, 0.0),
+			life: Projectile::LIFE,
+		});
+		engine.move_projectiles();
+		assert!(engine.projectiles.is_empty());
+		assert_eq!(engine.mines.iter().filter(|m| m.is_alive()).count(), 1);
+		assert_eq!(engine.bonus, FOE_SCORE);
+	}
+
+	#[test]
+	fn asteroids_split_until_they_are_small() {
+		let mut engine = GameEngine::new(GameData::simple(0, 1, 2, 0, 1, 0));
+		let big = Asteroid {
+			vert: engine.asteroid_vertices(1.0),
+			pos: Point::new(200.0, 300.0),
+			rot: 0.0,
+			dpos: Point::new(0.5, 0.0),
+			drot: 1.0,
+			radius: 20.0,
+			size: 1.0,
+		};
+		let small = Asteroid { size: 0.3, ..big.clone() };
+		engine.split_asteroid(big, &Point::new(0.0, -Projectile::SPEED));
+		assert_eq!(engine.asteroids.len(), 2);
+		for a in engine.asteroids.iter() {
+			assert!((a.size - 0.55).abs() < 1e-9);
+		}
+		assert_eq!(engine.bonus, 0);
+
+		engine.asteroids.clear();
+		engine.split_asteroid(small, &Point::new(0.0, -Projectile::SPEED));
+		assert!(engine.asteroids.is_empty());
+		assert_eq!(engine.bonus, ASTEROID_SCORE);
+	}
+
+	#[test]
+	fn walls_block_the_line_of_sight() {
+		let engine = GameEngine::new(GameData::fortress(0, 1, 2, 0, 1, 0));
+		assert!(!engine.line_of_sight(&Point::new(45.0, 200.0), &Point::new(45.0, 320.0)));
+		assert!(engine.line_of_sight(&Point::new(150.0, 200.0), &Point::new(150.0, 320.0)));
+		assert!(!engine.line_of_sight(&Point::new(150.0, 590.0), &Point::new(150.0, 620.0)));
+	}
+
+	#[test]
+	fn turret_aim_stays_within_its_arc() {
+		let mut engine = GameEngine::new(GameData::fortress(0, 1, 2, 0, 1, 0));
+		// nearly straight above the first turret, which faces right
+		let ship = Point::new(110.0, 100.0);
+		for _ in 0..100 {
+			engine.pos = ship.clone();
+			engine.move_hostiles();
+			assert!(engine.turrets[0].aim().abs() <= engine.turrets[0].arc);
+		}
+		assert!((engine.turrets[0].aim() + engine.turrets[0].arc).abs() < 1e-9);
+	}
+
+	#[test]
+	fn foes_are_the_same_in_every_run() {
+		let run = || {
+			let mut engine = GameEngine::new(GameData::fortress(0, 1, 2, 0, 1, 0));
+			let mut shots = vec!();
+			for _ in 0..300 {
+				engine.move_step();
+				engine.iter_bullets(|b| shots.push((b.position().x(), b.position().y())));
+			}
+			shots
+		};
+		let first = run();
+		assert!(!first.is_empty());
+		assert_eq!(first, run());
+	}
+
+	#[test]
+	fn mines_stop_once_landed() {
+		let mut engine = GameEngine::new(GameData::fortress(0, 1, 2, 0, 1, 0));
+		hover_over(&mut engine, 0);
+		settle(&mut engine);
+		assert!(engine.has_landed());
+		let mut pos = engine.ship_pos();
+		pos.add(&Point::new(-20.0, 0.0));
+		engine.mines[0].pos = pos.clone();
+		engine.mines[0].speed = Point::new(Mine::MAX_SPEED, 0.0);
+		engine.move_step();
+		assert_eq!((engine.mines[0].pos.x(), engine.mines[0].pos.y()), (pos.x(), pos.y()));
+	}
+
+	#[test]
+	fn laser_gates_follow_their_cycle() {
+		let gate = LaserGate::new(Point::new(0.0, 0.0), Point::new(10.0, 0.0), 60, 90);
+		assert!(gate.cycle_on(0) && gate.cycle_on(59));
+		assert!(!gate.cycle_on(60) && !gate.cycle_on(149));
+		assert!(gate.cycle_on(150));
+
+		let gate = LaserGate::new(Point::new(0.0, 0.0), Point::new(10.0, 0.0), 40, 40).with_phase(40);
+		assert!(!gate.cycle_on(0) && !gate.cycle_on(39));
+		assert!(gate.cycle_on(40) && gate.cycle_on(79));
+		assert!(!gate.cycle_on(80));
+	}
+
+	#[test]
+	#[should_panic]
+	fn laser_gates_need_a_cycle() {
+		LaserGate::new(Point::new(0.0, 0.0), Point::new(10.0, 0.0), 0, 0);
+	}
+
+	#[test]
+	fn switches_hold_for_their_steps() {
+		let mut timed = Switch::zone(vec!(), 1).for_steps(3);
+		let mut latched = Switch::zone(vec!(), 2);
+		timed.activate();
+		latched.activate();
+		for _ in 0..2 {
+			timed.move_step();
+			latched.move_step();
+			assert!(timed.is_on());
+		}
+		timed.move_step();
+		assert!(!timed.is_on());
+		for _ in 0..100 {
+			latched.move_step();
+		}
+		assert!(latched.is_on());
+	}
+
+	#[test]
+	fn doors_slide_open_and_back() {
+		let mut engine = GameEngine::new(GameData::vault(0, 1, 2, 0, 1, 0));
+		let start = engine.pos.clone();
+		let closed = |e: &GameEngine| e.config.get_walls(e.step, &e.trig)[7][0].clone();
+		let run = |e: &mut GameEngine| {
+			for _ in 0..60 {
+				e.pos = start.clone();
+				e.speed = Point::new(0.0, 0.0);
+				e.move_step();
+			}
+		};
+
+		engine.switches[1].activate();
+		engine.move_step();
+		assert!(engine.doors[7] > 0.0 && engine.doors[7] < 1.0);
+		run(&mut engine);
+		assert_eq!(engine.doors[7], 1.0);
+		assert!((engine.walls[7][0].x() - closed(&engine).x() + 140.0).abs() < 1e-9);
+
+		engine.switches[1].on = false;
+		run(&mut engine);
+		assert_eq!(engine.doors[7], 0.0);
+		assert!((engine.walls[7][0].x() - closed(&engine).x()).abs() < 1e-9);
+	}
+
+	#[test]
+	fn offset_takes_the_short_way_around() {
+		let engine = GameEngine::new(GameData::warp(0, 1, 2, 0, 1, 0));
+		let (w, h) = (engine.area_width(), engine.area_height());
+		let d = engine.offset(&Point::new(10.0, 10.0), &Point::new(w - 10.0, 10.0));
+		assert!((d.x() + 20.0).abs() < 1e-9 && d.y().abs() < 1e-9);
+		let d = engine.offset(&Point::new(10.0, h - 5.0), &Point::new(30.0, 5.0));
+		assert!((d.x() - 20.0).abs() < 1e-9 && (d.y() - 10.0).abs() < 1e-9);
+
+		let engine = GameEngine::new(GameData::simple(0, 1, 2, 0, 1, 0));
+		let d = engine.offset(&Point::new(10.0, 10.0), &Point::new(w - 10.0, 10.0));
+		assert!((d.x() - w + 20.0).abs() < 1e-9);
+	}
+
+	#[test]
+	fn ship_flies_across_the_seam() {
+		let mut engine = GameEngine::new(GameData::warp(0, 1, 2, 0, 1, 0));
+		let w = engine.area_width();
+		engine.pos = Point::new(w - 5.0, 100.0);
+		engine.speed = Point::new(3.0, 0.0);
+		for _ in 0..5 {
+			engine.move_step();
+			assert!(!engine.has_collided());
+		}
+		assert!(engine.ship_pos().x() > 0.0 && engine.ship_pos().x() < 20.0);
+	}
+
+	#[test]
+	fn ship_hits_what_is_across_the_seam() {
+		let mut engine = GameEngine::new(GameData::warp(0, 1, 2, 0, 1, 0));
+		let w = engine.area_width();
+		engine.mines = vec![Mine::new(Point::new(2.0, 300.0))];
+
+		// the tail sticks out on the right, into the mine on the left
+		engine.pos = Point::new(w - 3.0, 295.0);
+		let ship = engine.ship_shape();
+		assert!(!engine.collision(&ship, &[]));
+		assert!(engine.hostile_hit(&ship));
+
+		engine.walls.push(vec!(
+			Point::new(0.0, 400.0), Point::new(20.0, 400.0), Point::new(20.0, 420.0), Point::new(0.0, 420.0),
+		));
+		engine.pos = Point::new(w - 3.0, 405.0);
+		let ship = engine.ship_shape();
+		assert!(engine.collision(&ship, &[]));
+		engine.pos = Point::new(w - 30.0, 405.0);
+		let ship = engine.ship_shape();
+		assert!(!engine.collision(&ship, &[]));
+	}
+
+	#[test]
+	fn mission_needs_the_stages_in_order() {
+		let mut engine = GameEngine::new(GameData::relay(0, 1, 2, 0, 1, 0));
+		hover_over(&mut engine, 1);
+		settle(&mut engine);
+		assert!(!engine.has_landed());
+		assert!(!engine.finished());
+		assert_eq!(engine.stage(), 0);
+
+		hover_over(&mut engine, 0);
+		settle(&mut engine);
+		assert_eq!(engine.stage(), 1);
+		assert!(!engine.finished());
+
+		hover_over(&mut engine, 1);
+		settle(&mut engine);
+		assert!(engine.has_landed());
+		assert!(engine.finished());
+	}
+}
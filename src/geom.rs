@@ -4,38 +4,45 @@ use crate::common::Fpt;
 use crate::deg2rad;
 use std::f64;
 
-struct Angle { pub rad: f64, pub sin: f64, pub cos: f64 }
+// angles are float degrees, clockwise on screen
+pub type Deg = Fpt;
 
+// table entries per degree
+const TRIG_RES: usize = 16;
+
+// Sine and cosine come from a table filled once and linearly interpolated
+// between entries.
+#[derive(Clone)]
 pub struct Trig {
-	data: Vec<Angle>
+	sin: Vec<f64>
 }
 impl Trig {
 	pub fn new() -> Self {
-		let mut d = Vec::with_capacity(360);
-		for i in 0..360 {
-			let r = deg2rad!(i);
-			d.push(Angle {
-				rad: r,
-				sin: r.sin(),
-				cos: r.cos()
-			});
+		// one extra entry so the interpolation never wraps
+		let n = 360 * TRIG_RES + 1;
+		let mut d = Vec::with_capacity(n);
+		for i in 0..n {
+			d.push(deg2rad!(i as f64 / TRIG_RES as f64).sin());
 		}
-		Self { data: d }
+		Self { sin: d }
 	}
-	fn el(&self, deg: i32) -> &Angle {
-		let mut deg = deg.clone();
-		while deg < 0 { deg += 360; }
-		let idx = (deg % 360) as usize;
-		&self.data[idx]
+	fn lookup(&self, deg: Deg) -> f64 {
+		let pos = deg.rem_euclid(360.0) * TRIG_RES as f64;
+		let idx = pos.floor();
+		let t = pos - idx;
+		let idx = (idx as usize).min(360 * TRIG_RES - 1);
+		self.sin[idx] + (self.sin[idx + 1] - self.sin[idx]) * t
 	}
-	pub fn rad(&self, deg: i32) -> f64 { self.el(deg).rad }
-	pub fn sin(&self, deg: i32) -> f64 { self.el(deg).sin }
-	pub fn cos(&self, deg: i32) -> f64 { self.el(deg).cos }
+	pub fn rad(&self, deg: Deg) -> f64 { deg2rad!(deg) }
+	pub fn sin(&self, deg: Deg) -> f64 { self.lookup(deg) }
+	pub fn cos(&self, deg: Deg) -> f64 { self.lookup(deg + 90.0) }
 
-	pub fn rot(&self, p: &Point, deg: i32) -> Point {
+	pub fn rot(&self, p: &Point, deg: Deg) -> Point {
+		let sin = self.sin(deg);
+		let cos = self.cos(deg);
 		Point::new(
-			self.cos(deg)*p.x - self.sin(deg)*p.y,
-			self.sin(deg)*p.x + self.cos(deg)*p.y,
+			cos*p.x - sin*p.y,
+			sin*p.x + cos*p.y,
 		)
 	}
 }
@@ -87,6 +94,14 @@ impl Point {
 		self.y *= amt;
 	}
 
+	pub fn dot(&self, p: &Point) -> Fpt {
+		self.x*p.x + self.y*p.y
+	}
+	// z of the 3d cross product
+	pub fn cross(&self, p: &Point) -> Fpt {
+		self.x*p.y - self.y*p.x
+	}
+
 	pub fn flipx(&mut self) {
 		self.x = -self.x;
 	}
@@ -180,6 +195,46 @@ pub fn collide(s1: &Vec<Point>, s2: &Vec<Point>) -> bool {
 	false
 }
 
+// closest point to p on the outline of the shape: edge index, position
+// along the edge (0.0 to 1.0) and the point itself
+pub fn nearest_edge(shape: &[Point], p: &Point) -> Option<(usize, Fpt, Point)> {
+	let mut rv = None;
+	let mut best = Fpt::MAX;
+	for i in 0..shape.len() {
+		let a = &shape[i];
+		let b = &shape[(i + 1) % shape.len()];
+		let ab = Point::new(b.x - a.x, b.y - a.y);
+		let len2 = ab.length2();
+		let t = if len2 > 0.0 {
+			Fpt::max(0.0, Fpt::min(1.0, Point::new(p.x - a.x, p.y - a.y).dot(&ab) / len2))
+		} else {
+			0.0
+		};
+		let q = Point::new(a.x + t * ab.x, a.y + t * ab.y);
+		let d = Point::new(p.x - q.x, p.y - q.y).length2();
+		if d < best {
+			best = d;
+			rv = Some((i, t, q));
+		}
+	}
+	rv
+}
+
+// even-odd rule, works for concave shapes too
+pub fn inside_shape(shape: &[Point], p: &Point) -> bool {
+	let mut rv = false;
+	for i in 0..shape.len() {
+		let a = &shape[i];
+		let b = &shape[(i + 1) % shape.len()];
+		// only edges crossing the horizontal through p count, so a.y and
+		// b.y differ
+		if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) * (b.x - a.x) / (b.y - a.y) {
+			rv = !rv;
+		}
+	}
+	rv
+}
+
 pub fn inside_rect(p: &Point, x0: Fpt, y0: Fpt, x1: Fpt, y1: Fpt) -> bool {
 	if p.x < x0 { return false; }
 	if p.x > x1 { return false; }
@@ -187,3 +242,71 @@ pub fn inside_rect(p: &Point, x0: Fpt, y0: Fpt, x1: Fpt, y1: Fpt) -> bool {
 	if p.y > y1 { return false; }
 	true
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn shape(pts: &[(Fpt, Fpt)]) -> Vec<Point> {
+		pts.iter().map(|(x, y)| Point::new(*x, *y)).collect()
+	}
+
+	#[test]
+	fn nearest_edge_of_a_square() {
+		let sq = shape(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+		let (i, t, q) = nearest_edge(&sq, &Point::new(4.0, -3.0)).unwrap();
+		assert_eq!(i, 0);
+		assert!((t - 0.4).abs() < 1e-9);
+		assert!((q.x() - 4.0).abs() < 1e-9 && q.y().abs() < 1e-9);
+
+		// past the corner it clamps to the end of the edge
+		let (_, t, q) = nearest_edge(&sq, &Point::new(13.0, 14.0)).unwrap();
+		assert!(t == 0.0 || t == 1.0);
+		assert!((q.x() - 10.0).abs() < 1e-9 && (q.y() - 10.0).abs() < 1e-9);
+
+		// from the inside, the closing edge counts too
+		let (i, _, q) = nearest_edge(&sq, &Point::new(1.0, 5.0)).unwrap();
+		assert_eq!(i, 3);
+		assert!(q.x().abs() < 1e-9 && (q.y() - 5.0).abs() < 1e-9);
+	}
+
+	#[test]
+	fn nearest_edge_of_degenerate_shapes() {
+		assert!(nearest_edge(&[], &Point::new(1.0, 1.0)).is_none());
+
+		let dot = shape(&[(3.0, 4.0)]);
+		let (i, t, q) = nearest_edge(&dot, &Point::new(0.0, 0.0)).unwrap();
+		assert_eq!((i, t), (0, 0.0));
+		assert!((q.x() - 3.0).abs() < 1e-9 && (q.y() - 4.0).abs() < 1e-9);
+
+		// a segment given as a triangle with a repeated point, like beams
+		let seg = shape(&[(0.0, 0.0), (10.0, 0.0), (10.0, 0.0)]);
+		let (_, _, q) = nearest_edge(&seg, &Point::new(5.0, 2.0)).unwrap();
+		assert!((q.x() - 5.0).abs() < 1e-9 && q.y().abs() < 1e-9);
+	}
+
+	#[test]
+	fn inside_concave_shape() {
+		// U shape open at the top
+		let u = shape(&[(0.0, 0.0), (3.0, 0.0), (3.0, 7.0), (7.0, 7.0), (7.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+		assert!(inside_shape(&u, &Point::new(1.0, 5.0)));
+		assert!(inside_shape(&u, &Point::new(9.0, 5.0)));
+		assert!(inside_shape(&u, &Point::new(5.0, 8.0)));
+		assert!(!inside_shape(&u, &Point::new(5.0, 3.0)));
+		assert!(!inside_shape(&u, &Point::new(-1.0, 5.0)));
+		assert!(!inside_shape(&u, &Point::new(5.0, 11.0)));
+	}
+
+	#[test]
+	fn inside_degenerate_shapes() {
+		assert!(!inside_shape(&[], &Point::new(0.0, 0.0)));
+		assert!(!inside_shape(&shape(&[(1.0, 1.0)]), &Point::new(1.0, 1.0)));
+		// flat: every edge is horizontal, nothing is ever inside
+		let flat = shape(&[(0.0, 5.0), (10.0, 5.0), (10.0, 5.0)]);
+		assert!(!inside_shape(&flat, &Point::new(5.0, 5.0)));
+		// level with a horizontal edge, beside it
+		let sq = shape(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+		assert!(!inside_shape(&sq, &Point::new(15.0, 0.0)));
+		assert!(!inside_shape(&sq, &Point::new(-5.0, 10.0)));
+	}
+}
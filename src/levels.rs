@@ -3,34 +3,150 @@ use std::cmp::max;
 use crate::common::*;
 use crate::geom::Point;
 use crate::pt;
-use crate::engine::Wind;
-use crate::geom::Trig;
+use crate::engine::{Wind,WindSchedule,GravityWell,FluidZone,Pickup,PickupKind,Turret,Mine,Switch,LaserGate,Portal};
+use crate::geom::{Trig,Deg};
 
+#[derive(Clone)]
+struct Transform {
+	offset: Point,
+	angle: Deg,
+	scale: Fpt,
+}
+impl Transform {
+	fn identity() -> Self {
+		Self { offset: pt!(0,0), angle: 0.0, scale: 1.0 }
+	}
+	fn new(offset: Point, angle: Deg, scale: Fpt) -> Self {
+		Self { offset, angle, scale }
+	}
+	fn combine(&mut self, o: &Transform) {
+		self.offset.add(&o.offset);
+		self.angle += o.angle;
+		self.scale *= o.scale;
+	}
+	fn lerp(a: &Transform, b: &Transform, t: Fpt) -> Self {
+		Self {
+			offset: pt!(
+				a.offset.x() + (b.offset.x() - a.offset.x()) * t,
+				a.offset.y() + (b.offset.y() - a.offset.y()) * t
+			),
+			angle: a.angle + (b.angle - a.angle) * t,
+			scale: a.scale + (b.scale - a.scale) * t,
+		}
+	}
+}
+
+#[derive(Clone)]
+enum Easing {
+	Linear,
+	InOut,
+}
+impl Easing {
+	fn apply(&self, t: Fpt, tr: &Trig) -> Fpt {
+		match self {
+			Easing::Linear => t,
+			Easing::InOut => (1.0 - tr.cos(180.0 * t)) / 2.0,
+		}
+	}
+}
+
+// Every motion is a function of the position in its period, the shape
+// of the wall is relative to the wall base.
+#[derive(Clone)]
 enum MotionImpl {
 	Static,
-	Rot,
-	XCos
+	// one full turn per period
+	Rot(bool),
+	// cosine oscillation of the given amplitude on each axis
+	Osc(Point),
+	// back and forth along the polyline, offsets from the base
+	Path(Vec<Point>, Easing),
+	// swing of the given amplitude in degrees around the base
+	Pendulum(Deg),
+	// scale oscillating by the given fraction around the base
+	Pulse(Fpt),
+	// transforms reached at the given steps, the last one closes the
+	// period and should match the first
+	Keyframes(Vec<(u32, Transform)>, Easing),
+}
+impl MotionImpl {
+	fn transform(&self, t: u32, period: u32, tr: &Trig) -> Transform {
+		let period = period.max(1);
+		let a = 360.0 * (t % period) as Deg / period as Deg;
+		match self {
+			MotionImpl::Static => Transform::identity(),
+			MotionImpl::Rot(ccw) => Transform::new(pt!(0,0), if *ccw { -a } else { a }, 1.0),
+			MotionImpl::Osc(ampl) => {
+				let c = tr.cos(a);
+				Transform::new(pt!(ampl.x() * c, ampl.y() * c), 0.0, 1.0)
+			},
+			MotionImpl::Path(points, easing) => {
+				// triangle wave, there and back in a period
+				let u = 1.0 - (2.0 * (t % period) as Fpt / period as Fpt - 1.0).abs();
+				Transform::new(Self::along(points, easing.apply(u, tr)), 0.0, 1.0)
+			},
+			MotionImpl::Pendulum(ampl) => Transform::new(pt!(0,0), ampl * tr.sin(a), 1.0),
+			MotionImpl::Pulse(ampl) => Transform::new(pt!(0,0), 0.0, 1.0 + ampl * tr.sin(a)),
+			MotionImpl::Keyframes(keys, easing) => {
+				let last = keys.last().map(|k| k.0).unwrap_or(0).max(1);
+				let tm = t % last;
+				for w in keys.windows(2) {
+					let (s0, k0) = &w[0];
+					let (s1, k1) = &w[1];
+					if tm >= *s0 && tm < *s1 {
+						let u = (tm - s0) as Fpt / (s1 - s0) as Fpt;
+						return Transform::lerp(k0, k1, easing.apply(u, tr));
+					}
+				}
+				keys.first().map(|k| k.1.clone()).unwrap_or(Transform::identity())
+			},
+		}
+	}
+
+	fn along(points: &[Point], u: Fpt) -> Point {
+		let seg = |a: &Point, b: &Point| Fpt::sqrt((b.x() - a.x()).powi(2) + (b.y() - a.y()).powi(2));
+		let total: Fpt = points.windows(2).map(|w| seg(&w[0], &w[1])).sum();
+		let mut left = u * total;
+		for w in points.windows(2) {
+			let l = seg(&w[0], &w[1]);
+			if left <= l && l > 0.0 {
+				let k = left / l;
+				return pt!(
+					w[0].x() + (w[1].x() - w[0].x()) * k,
+					w[0].y() + (w[1].y() - w[0].y()) * k
+				);
+			}
+			left -= l;
+		}
+		points.last().cloned().unwrap_or(pt!(0,0))
+	}
 }
 
+#[derive(Clone)]
 struct Wall {
 	shape: Vec<Point>,
 	base: Point,
-	motion: MotionImpl,
-	ampl: Fpt,
-	freq: i32,
-	step: u32,
+	// several motions are combined, each one with its own period
+	motions: Vec<(MotionImpl, u32)>,
 	init: u32,
+	// the top edge (first two points) can be stood on
+	platform: bool,
+	// signal opening it and how far it slides once open
+	door: Option<(u32, Point)>,
 }
 impl Wall {
 	pub fn fixed(shape: Vec<Point>) -> Self {
+		Self::moving(pt!(0,0), shape, MotionImpl::Static, 1)
+	}
+
+	fn moving(base: Point, shape: Vec<Point>, motion: MotionImpl, period: u32) -> Self {
 		Self {
-			shape: shape,
-			motion: MotionImpl::Static,
-			base: pt!(0,0),
-			ampl: 0.0,
-			freq: 0,
-			step: 0,
+			shape,
+			base,
+			motions: vec![(motion, period)],
 			init: 0,
+			platform: false,
+			door: None,
 		}
 	}
 
@@ -45,49 +161,83 @@ impl Wall {
 			_ => 4
 		}
 	}
+	fn freq_period(freq: u8) -> u32 {
+		360u32 / Self::normalize_freq(freq) as u32
+	}
 
 	pub fn xcos(shape: Vec<Point>, ampl: Fpt, freq: u8) -> Self {
-		let f = Self::normalize_freq(freq);
-		Self {
-			shape: shape,
-			motion: MotionImpl::XCos,
-			base: pt!(0,0),
-			ampl: ampl,
-			freq: f as i32,
-			step: 360u32 / f as u32,
-			init: 0,
-		}
+		Self::moving(pt!(0,0), shape, MotionImpl::Osc(Point::new(ampl, 0.0)), Self::freq_period(freq))
 	}
-	pub fn rotating(base: Point, shape: Vec<Point>, init: u32, ccw: bool, freq: u8) -> Self {
-		let f = Self::normalize_freq(freq);
-		Self {
-			shape: shape,
-			motion: MotionImpl::Rot,
-			base: base,
-			ampl: if ccw { -1.0 } else { 1.0 },
-			freq: f as i32,
-			step: 360u32 / f as u32,
-			init,
-		}
+	pub fn ycos(shape: Vec<Point>, ampl: Fpt, freq: u8) -> Self {
+		Self::moving(pt!(0,0), shape, MotionImpl::Osc(Point::new(0.0, ampl)), Self::freq_period(freq))
+	}
+	pub fn oscillating(shape: Vec<Point>, ampl: Point, period: u32) -> Self {
+		Self::moving(pt!(0,0), shape, MotionImpl::Osc(ampl), period)
+	}
+	// a full turn every period steps
+	pub fn rotating(base: Point, shape: Vec<Point>, init: u32, ccw: bool, period: u32) -> Self {
+		Self::moving(base, shape, MotionImpl::Rot(ccw), period).with_phase(init)
+	}
+	pub fn following(shape: Vec<Point>, path: Vec<Point>, smooth: bool, period: u32) -> Self {
+		let easing = if smooth { Easing::InOut } else { Easing::Linear };
+		Self::moving(pt!(0,0), shape, MotionImpl::Path(path, easing), period)
+	}
+	pub fn pendulum(base: Point, shape: Vec<Point>, ampl: Deg, period: u32) -> Self {
+		Self::moving(base, shape, MotionImpl::Pendulum(ampl), period)
+	}
+	pub fn pulsing(base: Point, shape: Vec<Point>, ampl: Fpt, period: u32) -> Self {
+		Self::moving(base, shape, MotionImpl::Pulse(ampl), period)
+	}
+	// keys are (step, offset, angle, scale)
+	pub fn keyframed(base: Point, shape: Vec<Point>, keys: Vec<(u32, Point, Deg, Fpt)>, smooth: bool) -> Self {
+		let easing = if smooth { Easing::InOut } else { Easing::Linear };
+		let keys = keys.into_iter().map(|(s, o, a, sc)| (s, Transform::new(o, a, sc))).collect::<Vec<_>>();
+		let period = keys.last().map(|k| k.0).unwrap_or(1);
+		Self::moving(base, shape, MotionImpl::Keyframes(keys, easing), period)
+	}
+
+	// adds the motion of another wall on top of this one
+	pub fn and(mut self, other: Wall) -> Self {
+		self.motions.extend(other.motions);
+		self
+	}
+	// steps the motion is ahead of the game
+	pub fn with_phase(mut self, init: u32) -> Self {
+		self.init = init;
+		self
+	}
+
+	// slides by offset while the signal id is on, and back once it is off
+	pub fn door(mut self, id: u32, offset: Point) -> Self {
+		self.door = Some((id, offset));
+		self
+	}
+
+	// the ship can rest on the top edge, from the first to the second point
+	pub fn landable(mut self) -> Self {
+		self.platform = true;
+		self
 	}
 
 	pub fn shape(&self, gstep: u32, tr: &Trig) -> Vec<Point> {
-		match self.motion {
-			MotionImpl::Static => self.shape.clone(),
-			MotionImpl::XCos => {
-				let dx = tr.cos(self.freq * (gstep % self.step) as i32) * self.ampl;
-				self.shape.iter().map(|p| pt!(p.x()+dx, p.y())).collect()
-			},
-			MotionImpl::Rot => {
-				let f = if self.ampl > 0.0 { self.freq } else { 360 - self.freq };
-				let cos = tr.cos(f * ((self.init+gstep) % self.step) as i32);
-				let sin = tr.sin(f * ((self.init+gstep) % self.step) as i32);
-				self.shape.iter().map(|p| pt!(
-						p.x()*cos - p.y()*sin + self.base.x(),
-						p.x()*sin + p.y()*cos + self.base.y()
-				)).collect()
-			}
+		self.place(&self.shape, gstep, tr)
+	}
+
+	// moves points given relative to the base along with the wall
+	fn place(&self, points: &[Point], gstep: u32, tr: &Trig) -> Vec<Point> {
+		let t = self.init + gstep;
+		let mut tf = Transform::identity();
+		for (m, period) in self.motions.iter() {
+			tf.combine(&m.transform(t, *period, tr));
 		}
+		points.iter().map(|p| {
+			let mut p = p.clone();
+			p.mul(tf.scale);
+			let mut p = tr.rot(&p, tf.angle);
+			p.add(&self.base);
+			p.add(&tf.offset);
+			p
+		}).collect()
 	}
 }
 
@@ -98,15 +248,71 @@ macro_rules! xcwall {
 	( $($shape:expr),* ) => { Wall::xcos( vec!( $($shape),* ), 20.0, 4 ) }
 }
 
+#[derive(Clone)]
+pub struct Pad {
+	x0: Fpt,
+	x1: Fpt,
+	y: Fpt,
+	// the pad moves with this wall, the position is given with the wall
+	// at rest
+	wall: Option<usize>,
+	multiplier: Fpt,
+	// fuel stock of a refuelling pad, the ship can rest there and take
+	// off again
+	refuel: Fpt,
+}
+impl Pad {
+	pub fn new(x0: Fpt, x1: Fpt, y: Fpt) -> Self {
+		Self {
+			x0,
+			x1,
+			y,
+			wall: None,
+			multiplier: 1.0,
+			refuel: 0.0,
+		}
+	}
+	pub fn on_wall(mut self, idx: usize) -> Self {
+		self.wall = Some(idx);
+		self
+	}
+	pub fn worth(mut self, multiplier: Fpt) -> Self {
+		self.multiplier = multiplier;
+		self
+	}
+	pub fn refuel(mut self, stock: Fpt) -> Self {
+		self.refuel = stock;
+		self
+	}
+	pub fn multiplier(&self) -> Fpt { self.multiplier }
+	pub fn fuel_stock(&self) -> Fpt { self.refuel }
+	pub fn is_refuel(&self) -> bool { self.refuel > 0.0 }
+}
+
+// Attitude model with angular momentum, when missing the ship rotates
+// at a fixed rate while the controls are held.
+#[derive(Clone)]
+pub struct Handling {
+	// angular acceleration of the RCS thrusters, degrees per step squared
+	pub rcs_pow: Fpt,
+	// fraction of the angular velocity lost on each step
+	pub damping: Fpt,
+	// the RCS counters any spin when the pilot is not rotating
+	pub stabilize: bool,
+	pub monoprop: Fpt,
+}
+
+#[derive(Clone)]
 pub struct GameData {
 	pub area: Point,
 	pub viewport: Option<Point>,
 	pub viewport_pos0: Option<Point>,
 	pub pos0: Point,
 	pub speed0: Point,
-	pub target_y: Fpt,
-	pub target_x0: Fpt,
-	pub target_x1: Fpt,
+	pub pads: Vec<Pad>,
+	// pads to land on in order, the last one ends the mission; when empty
+	// any landing pad ends the game
+	pub stages: Vec<usize>,
 	pub num_asteroids: u32,
 	pub asteroid_pos0: Point,
 	pub asteroid_area: Point,
@@ -117,18 +323,33 @@ pub struct GameData {
 	pub full_fuel: Fpt,
 	pub thrust_pow: Fpt,
 	pub gravity: Point,
+	pub gravity_wells: Vec<GravityWell>,
 	pub friction: Fpt,
+	pub handling: Option<Handling>,
+	// mass of a full tank relative to the dry ship, 0 disables the mass
+	// model
+	pub fuel_mass: Fpt,
 	walls: Vec<Wall>,
 	pub winds: Vec<Wind>,
+	pub fluids: Vec<FluidZone>,
+	pub pickups: Vec<Pickup>,
+	// where the cargo pod waits to be towed to the pad
+	pub cargo: Option<Point>,
+	// shots available, the ship is unarmed with none
+	pub ammo: u32,
+	pub turrets: Vec<Turret>,
+	pub mines: Vec<Mine>,
+	pub switches: Vec<Switch>,
+	pub lasers: Vec<LaserGate>,
+	pub portals: Vec<Portal>,
+	// leaving the area on one side enters it from the opposite one
+	pub wrap: bool,
 }
 
 impl GameData {
 /*    pub fn area(&self) -> &Point { &self.area }
     pub fn pos0(&self) -> &Point { &self.pos0 }
     pub fn speed0(&self) -> &Point { &self.speed0 }
-    pub fn target_y(&self) -> Fpt { self.target_y }
-    pub fn target_x0(&self) -> Fpt { self.target_x0 }
-    pub fn target_x1(&self) -> Fpt { self.target_x1 }
     pub fn initial_fuel(&self) -> Fpt { self.initial_fuel }
     pub fn num_asteroids(&self) -> u32 { self.num_asteroids }
     pub fn gravity(&self) -> &Point { &self.gravity }
@@ -206,69 +427,85 @@ impl GameData {
 		}
 	}
 
-	pub fn simple(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32) -> Self {
-		let wi = 320;
-		let w = wi as Fpt;
-		let h = 640 as Fpt;
-		let na = Self::ast_default(ast);
-		let f = Self::fuel_default(fuel);
-		let fr = Self::friction_default(fric);
-		let th = Self::thrust_default(thrust);
-		let grav = Self::gravity_default(gravity);
+	fn handling_default(handling: u32) -> Option<Handling> {
+		match handling {
+			0 => None,
+			1 => Some(Handling {
+				rcs_pow: 0.6,
+				damping: 0.05,
+				stabilize: true,
+				monoprop: 400.0,
+			}),
+			2 => Some(Handling {
+				rcs_pow: 0.4,
+				damping: 0.0,
+				stabilize: false,
+				monoprop: 250.0,
+			}),
+			_ => None
+		}
+	}
 
+	// defaults of every map, each one sets what differs with the struct
+	// update syntax
+	fn base(area: Point, ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
+		let f = Self::fuel_default(fuel);
 		Self {
-			area: Point::new(w,h),
+			area: area.clone(),
 			viewport: None, viewport_pos0: None,
-			pos0: Point::new(160.0, 50.0),
+			pos0: Point::new(area.x() / 2.0, 50.0),
 			speed0: Point::new(0.0, 0.0),
-			target_y: 600.0,
-			target_x0: 100.0,
-			target_x1: 220.0,
-			num_asteroids: na,
+			pads: vec!(),
+			stages: vec!(),
+			num_asteroids: Self::ast_default(ast),
 			asteroid_pos0: Point::new(0.0, 120.0),
-			asteroid_area: Point::new(w, h - 120.0),
+			asteroid_area: Point::new(area.x(), area.y() - 120.0),
 			levelling_rot: 15,
 			levelling_speed_x: 3.5,
 			levelling_speed_y: 2.5,
 			initial_fuel: f,
 			full_fuel: max(f,500) as Fpt,
-			thrust_pow: th,
-			gravity: Point::new(0.0, 0.06 * grav),
-			friction: fr,
+			thrust_pow: Self::thrust_default(thrust),
+			gravity: Point::new(0.0, 0.06 * Self::gravity_default(gravity)),
+			gravity_wells: vec!(),
+			friction: Self::friction_default(fric),
+			handling: Self::handling_default(handling),
+			fuel_mass: 0.0,
 			winds: vec!(),
+			fluids: vec!(),
+			pickups: vec!(),
+			cargo: None,
+			ammo: 0,
+			turrets: vec!(),
+			mines: vec!(),
+			switches: vec!(),
+			lasers: vec!(),
+			portals: vec!(),
+			wrap: false,
 			walls: vec!()
 		}
 	}
 
-	pub fn shifted(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32) -> Self {
+	pub fn simple(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
 		let wi = 320;
 		let w = wi as Fpt;
 		let h = 640 as Fpt;
-		let na = Self::ast_default(ast);
-		let f = Self::fuel_default(fuel);
-		let fr = Self::friction_default(fric);
-		let th = Self::thrust_default(thrust);
-		let grav = Self::gravity_default(gravity);
 
 		Self {
-			area: Point::new(w,h),
-			viewport: None, viewport_pos0: None,
 			pos0: Point::new(160.0, 50.0),
-			speed0: Point::new(0.0, 0.0),
-			target_y: 600.0,
-			target_x0: 100.0,
-			target_x1: 220.0,
-			num_asteroids: na,
-			asteroid_pos0: Point::new(0.0, 120.0),
-			asteroid_area: Point::new(w, h - 120.0),
-			levelling_rot: 15,
-			levelling_speed_x: 3.5,
-			levelling_speed_y: 2.5,
-			initial_fuel: f,
-			full_fuel: max(f,500) as Fpt,
-			thrust_pow: th,
-			gravity: Point::new(0.0, 0.06 * grav),
-			friction: fr,
+			pads: vec!(Pad::new(100.0, 220.0, 600.0)),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
+		}
+	}
+
+	pub fn shifted(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
+		let wi = 320;
+		let w = wi as Fpt;
+		let h = 640 as Fpt;
+
+		Self {
+			pos0: Point::new(160.0, 50.0),
+			pads: vec!(Pad::new(100.0, 220.0, 600.0)),
 			winds: vec!(
                     Wind::new(vec!(
 						pt!(0,100), pt!(w,100),
@@ -278,53 +515,36 @@ impl GameData {
 						pt!(0,200), pt!(w,200),
 						pt!(w,300), pt!(0,300)
 					),0.06,180),
-                    Wind::new(vec!(
+                    Wind::scheduled(vec!(
 						pt!(0,300), pt!(w,300),
 						pt!(w,400), pt!(0,400)
-					),0.055,0),
-                    Wind::new(vec!(
+					),0.055,0, WindSchedule::Sine { period: 240, min: 0.2, init: 0 }),
+                    Wind::scheduled(vec!(
 						pt!(0,400), pt!(w,400),
 						pt!(w,500), pt!(0,500)
-					),0.055,180),
-                    Wind::new(vec!(
+					),0.055,180, WindSchedule::Cycle { on: 160, off: 80, init: 40 }),
+                    Wind::scheduled(vec!(
 						pt!(0,500), pt!(w,500),
 						pt!(w,600), pt!(0,600)
-					),0.055,90),
+					),0.055,90, WindSchedule::Gusts { every: 120, boost: 2.0, seed: 500 }),
                 ),
-			walls: vec!()
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
 		}
 	}
 
-	pub fn tunnel(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32) -> Self {
+	pub fn tunnel(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
 		let wi = 350;
 		let w = wi as Fpt;
 		let h = 700 as Fpt;
-		let na = Self::ast_default(ast);
 		let f = Self::fuel_increased(fuel);
-		let fr = Self::friction_default(fric);
-		let th = Self::thrust_default(thrust);
-		let grav = Self::gravity_default(gravity);
 
 		Self {
-			area: Point::new(w,h),
-			viewport: None, viewport_pos0: None,
 			pos0: Point::new(40.0, 30.0),
-			speed0: Point::new(0.0, 0.0),
-			target_y: 650.0,
-			target_x0: 200.0,
-			target_x1: 350.0,
-			num_asteroids: na,
+			pads: vec!(Pad::new(200.0, 350.0, 650.0)),
 			asteroid_pos0: Point::new(60.0, 60.0),
 			asteroid_area: Point::new(w - 60.0, h - 60.0),
-			levelling_rot: 15,
-			levelling_speed_x: 3.5,
-			levelling_speed_y: 2.5,
 			initial_fuel: f,
 			full_fuel: max(f,750) as Fpt,
-			thrust_pow: th,
-			gravity: Point::new(0.0, 0.06 * grav),
-			friction: fr,
-			winds: vec!(),
 			walls: vec!(wall!(
 				pt!(0,80),
 				pt!(10,80),
@@ -371,7 +591,8 @@ impl GameData {
 				pt!(200,640),
 				pt!(200,700),
 				pt!(10,700)
-			))
+			)),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
 		}
 	}
 
@@ -383,79 +604,52 @@ impl GameData {
 		xcwall!(pt!(x-sz,y),pt!(x,y-sz),pt!(x+sz,y),pt!(x,y+sz))
 	}
 
-	pub fn windy_pillars(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32) -> Self {
+	pub fn windy_pillars(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
 		let wi = 300;
 		let w = wi as Fpt;
 		let h = 700 as Fpt;
-		let na = Self::ast_default(ast);
-		let f = Self::fuel_default(fuel);
-		let fr = Self::friction_default(fric);
-		let th = Self::thrust_default(thrust);
-		let grav = Self::gravity_default(gravity);
 
 		Self {
-			area: Point::new(w,h),
-			viewport: None, viewport_pos0: None,
 			pos0: Point::new(w / 2.0, 30.0),
-			speed0: Point::new(0.0, 0.0),
-			target_y: 650.0,
-			target_x0: 50.0,
-			target_x1: 250.0,
-			num_asteroids: na,
+			pads: vec!(Pad::new(50.0, 250.0, 650.0)),
 			asteroid_pos0: Point::new(0.0, 150.0),
 			asteroid_area: Point::new(w, h - 150.0),
-			levelling_rot: 15,
-			levelling_speed_x: 3.5,
-			levelling_speed_y: 2.5,
-			initial_fuel: f,
-			full_fuel: max(f,500) as Fpt,
-			thrust_pow: th,
-			gravity: Point::new(0.0, 0.06 * grav),
-			friction: fr,
-			winds: vec!(Wind::new(vec!(
+			winds: vec!(Wind::scheduled(vec!(
 						pt!(0,320),
 						pt!(w,320),
 						pt!(w,450),
 						pt!(0,450)
-					),0.04,0)),
+					),0.04,0, WindSchedule::Sweep { period: 400, ampl: 30.0, init: 0 })),
 			walls: vec!(
 				Self::pillar(150,340,30),
 				Self::pillar(80,440,30),
 				Self::pillar(220,440,30)
-			)
+			),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
 		}
 	}
 
-	pub fn cave(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32) -> Self {
+	pub fn cave(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
 		let wi = 320;
 		let w = wi as Fpt;
 		let h = 640 as Fpt;
-		let na = Self::ast_default(ast);
-		let f = Self::fuel_default(fuel);
-		let fr = Self::friction_default(fric);
-		let th = Self::thrust_default(thrust);
-		let grav = Self::gravity_default(gravity);
 
 		Self {
-			area: Point::new(w,h),
-			viewport: None, viewport_pos0: None,
 			pos0: Point::new(w / 2.0, 30.0),
-			speed0: Point::new(0.0, 0.0),
-			target_y: 600.0,
-			target_x0: 130.0,
-			target_x1: 190.0,
-			num_asteroids: na,
+			pads: vec!(Pad::new(130.0, 190.0, 600.0)),
 			asteroid_pos0: Point::new(0.0, 150.0),
 			asteroid_area: Point::new(w, h - 150.0),
-			levelling_rot: 15,
-			levelling_speed_x: 3.5,
-			levelling_speed_y: 2.5,
-			initial_fuel: f,
-			full_fuel: max(f,500) as Fpt,
-			thrust_pow: th,
-			gravity: Point::new(0.0, 0.06 * grav),
-			friction: fr,
-			winds: vec!(),
+			fluids: vec!(
+				// flooded bottom, the pad is under water
+				FluidZone::new(vec!(
+					pt!(0,480), pt!(w,480),
+					pt!(w,h), pt!(0,h)
+				), 0.94, 0.8),
+			),
+			pickups: vec!(
+				Pickup::new(PickupKind::Gem(250), pt!(40,560)),
+				Pickup::new(PickupKind::Gem(250), pt!(wi-40,560)),
+			),
 			walls: vec!(wall!(
 				pt!(0,50),
 				pt!(50,90),
@@ -507,39 +701,26 @@ impl GameData {
 				pt!(wi - 160, 400),
 				pt!(wi - 50, 420),
 				pt!(wi, 425)
-			))
+			)),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
 		}
 	}
 
-	pub fn choice(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32) -> Self {
+	pub fn choice(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
 		let wi = 300;
 		let w = wi as Fpt;
 		let h = 700 as Fpt;
-		let na = Self::ast_default(ast);
-		let f = Self::fuel_default(fuel);
-		let fr = Self::friction_default(fric);
-		let th = Self::thrust_default(thrust);
 		let grav = Self::gravity_default(gravity);
 
 		Self {
-			area: Point::new(w,h),
-			viewport: None, viewport_pos0: None,
 			pos0: Point::new(w / 2.0, 30.0),
-			speed0: Point::new(0.0, 0.0),
-			target_y: 660.0,
-			target_x0: 30.0,
-			target_x1: 270.0,
-			num_asteroids: na,
+			pads: vec!(
+				// easy wide pad under the updraft, narrow one past the crosswinds
+				Pad::new(20.0, 150.0, 660.0),
+				Pad::new(240.0, 270.0, 660.0).worth(3.0),
+			),
 			asteroid_pos0: Point::new(0.0, 150.0),
 			asteroid_area: Point::new(w, h - 150.0),
-			levelling_rot: 15,
-			levelling_speed_x: 3.5,
-			levelling_speed_y: 2.5,
-			initial_fuel: f,
-			full_fuel: max(f,500) as Fpt,
-			thrust_pow: th,
-			gravity: Point::new(0.0, 0.06 * grav),
-			friction: fr,
 			winds: vec!(Wind::new(vec!(
 						pt!(200,250),
 						pt!(250,250),
@@ -581,40 +762,24 @@ impl GameData {
 				pt!(200,200),
 				pt!(200,580),
 				pt!(190,580)
-			))
+			)),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
 		}
 	}
 
-	pub fn up(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32) -> Self {
+	pub fn up(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
 		let wi = 360;
 		let w = wi as Fpt;
 		let h = 700 as Fpt;
-		let na = Self::ast_default(ast);
 		let f = Self::fuel_increased(fuel);
-		let fr = Self::friction_default(fric);
-		let th = Self::thrust_default(thrust);
-		let grav = Self::gravity_default(gravity);
 
 		Self {
-			area: Point::new(w,h),
-			viewport: None, viewport_pos0: None,
 			pos0: Point::new(180.0, 650.0),
-			speed0: Point::new(0.0, 0.0),
-			target_y: 100.0,
-			target_x0: 150.0,
-			target_x1: 210.0,
-			num_asteroids: na,
+			pads: vec!(Pad::new(150.0, 210.0, 100.0)),
 			asteroid_pos0: Point::new(0.0, 0.0),
 			asteroid_area: Point::new(w, 580.0),
-			levelling_rot: 15,
-			levelling_speed_x: 3.5,
-			levelling_speed_y: 2.5,
 			initial_fuel: f,
 			full_fuel: max(f,750) as Fpt,
-			thrust_pow: th,
-			gravity: Point::new(0.0, 0.06 * grav),
-			friction: fr,
-			winds: vec!(),
 			walls: vec!(wall!(
 				pt!(20,40),
 				pt!(50,50),
@@ -642,40 +807,35 @@ impl GameData {
 			wall!( pt!(80,560), pt!(90,530), pt!(100,560) ),
 			wall!( pt!(260,560), pt!(270,530), pt!(280,560) ),
 			),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
 		}
 	}
 
-	pub fn huge(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32) -> Self {
+	pub fn huge(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
 		let wi = 640;
 		let w = wi as Fpt;
 		let h = 1280 as Fpt;
 		let na = Self::ast_huge(ast);
 		let f = Self::fuel_increased(fuel);
-		let fr = Self::friction_default(fric);
-		let th = Self::thrust_default(thrust);
-		let grav = Self::gravity_default(gravity);
 
 		Self {
-			area: Point::new(w,h),
 			viewport: Some(pt!(320, 640)),
 			viewport_pos0: Some(pt!(-100,-100)),
 			pos0: Point::new(50.0, 50.0),
-			speed0: Point::new(0.0, 0.0),
-			target_y: 1240.0,
-			target_x0: 500.0,
-			target_x1: 600.0,
+			pads: vec!(
+				Pad::new(500.0, 600.0, 1240.0),
+				Pad::new(300.0, 360.0, 300.0).refuel(300.0),
+			),
 			num_asteroids: na,
-			asteroid_pos0: Point::new(0.0, 120.0),
-			asteroid_area: Point::new(w, h - 120.0),
-			levelling_rot: 15,
-			levelling_speed_x: 3.5,
-			levelling_speed_y: 2.5,
 			initial_fuel: f,
 			full_fuel: max(f,750) as Fpt,
-			thrust_pow: th,
-			gravity: Point::new(0.0, 0.06 * grav),
-			friction: fr,
-			winds: vec!(),
+			pickups: vec!(
+				Pickup::new(PickupKind::Fuel(150.0), pt!(120,500)).respawning(600),
+				Pickup::new(PickupKind::Fuel(150.0), pt!(520,700)).respawning(600),
+				Pickup::new(PickupKind::Shield(400), pt!(320,1000)),
+				Pickup::new(PickupKind::Gem(500), pt!(20,1100)),
+				Pickup::new(PickupKind::Gem(500), pt!(620,200)),
+			),
 			walls: vec!(
 				Self::pillar( 70,400,25),
 				Self::pillar(170,400,25),
@@ -698,7 +858,8 @@ impl GameData {
 				Self::pillar(370,800,25),
 				Self::pillar(470,800,25),
 				Self::pillar(570,800,25),
-			)
+			),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
 		}
 	}
 
@@ -707,39 +868,21 @@ impl GameData {
 			vec!(
 				pt!(-5,80),pt!(5,80),
 				pt!(5,-80),pt!(-5,-80),
-			), init, ccw, 1)
+			), init, ccw, 360)
 	}
 
-	pub fn gears(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32) -> Self {
+	pub fn gears(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
 		let wi = 360;
 		let w = wi as Fpt;
 		let h = 700 as Fpt;
-		let na = Self::ast_default(ast);
 		let f = Self::fuel_increased(fuel);
-		let fr = Self::friction_default(fric);
-		let th = Self::thrust_default(thrust);
-		let grav = Self::gravity_default(gravity);
 
 		Self {
-			area: Point::new(w,h),
-			viewport: None, viewport_pos0: None,
 			pos0: Point::new(180.0, 40.0),
-			speed0: Point::new(0.0, 0.0),
-			target_y: 650.0,
-			target_x0: 150.0,
-			target_x1: 210.0,
-			num_asteroids: na,
-			asteroid_pos0: Point::new(0.0, 120.0),
+			pads: vec!(Pad::new(150.0, 210.0, 650.0)),
 			asteroid_area: Point::new(w, 700.0),
-			levelling_rot: 15,
-			levelling_speed_x: 3.5,
-			levelling_speed_y: 2.5,
 			initial_fuel: f,
 			full_fuel: max(f,750) as Fpt,
-			thrust_pow: th,
-			gravity: Point::new(0.0, 0.06 * grav),
-			friction: fr,
-			winds: vec!(),
 			walls: vec!(
 				Self::gear(pt!(90,250), false, 0),
 				Self::gear(pt!(90,250), false, 90),
@@ -753,12 +896,351 @@ impl GameData {
 				Self::gear(pt!(wi-90,500), false, 60),
 				Self::gear(pt!(wi-90,500), false, 150),
 			),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
+		}
+	}
+
+	fn planetoid(pos: Point, radius: Fpt) -> Wall {
+		let tr = Trig::new();
+		Wall::fixed((0..24).map(|i| {
+			let a = (i * 15) as Deg;
+			pt!(pos.x() + radius * tr.cos(a), pos.y() + radius * tr.sin(a))
+		}).collect())
+	}
+
+	pub fn planetoid_map(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
+		let wi = 960;
+		let w = wi as Fpt;
+		let h = 1280 as Fpt;
+		let f = Self::fuel_increased(fuel);
+		let grav = Self::gravity_default(gravity);
+
+		Self {
+			viewport: Some(pt!(320, 640)),
+			viewport_pos0: Some(pt!(0,0)),
+			pos0: Point::new(80.0, 80.0),
+			speed0: Point::new(1.0, 0.0),
+			pads: vec!(Pad::new(410.0, 530.0, 1190.0)),
+			asteroid_pos0: Point::new(200.0, 0.0),
+			asteroid_area: Point::new(w - 200.0, 300.0),
+			initial_fuel: f,
+			full_fuel: max(f,750) as Fpt,
+			gravity: Point::new(0.0, 0.02 * grav),
+			gravity_wells: vec!(
+				GravityWell::new(pt!(480,560), 0.15 * grav, 2.0, 140.0),
+				GravityWell::new(pt!(770,960), 0.10 * grav, 2.0, 60.0),
+			),
+			walls: vec!(
+				Self::planetoid(pt!(480,560), 140.0),
+				Self::planetoid(pt!(770,960), 60.0),
+				wall!( pt!(380,1280), pt!(400,1195), pt!(540,1195), pt!(560,1280) ),
+			),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
+		}
+	}
+
+	fn block(x: i32, y: i32, hw: i32, hh: i32) -> Vec<Point> {
+		vec!(pt!(x-hw,y-hh), pt!(x+hw,y-hh), pt!(x+hw,y+hh), pt!(x-hw,y+hh))
+	}
+
+	pub fn machinery(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
+		let wi = 360;
+		let w = wi as Fpt;
+		let h = 700 as Fpt;
+		let f = Self::fuel_increased(fuel);
+
+		Self {
+			pos0: Point::new(180.0, 40.0),
+			pads: vec!(Pad::new(150.0, 210.0, 650.0)),
+			asteroid_area: Point::new(w, 700.0),
+			initial_fuel: f,
+			full_fuel: max(f,750) as Fpt,
+			walls: vec!(
+				// pistons
+				Wall::ycos(Self::block(60, 130, 30, 10), 40.0, 2),
+				Wall::ycos(Self::block(wi-60, 130, 30, 10), 40.0, 2).with_phase(90),
+
+				// diagonal shuttle
+				Wall::oscillating(Self::block(180, 220, 40, 6), pt!(60,25), 200),
+
+				// pendulum hanging from the middle
+				Wall::pendulum(pt!(180,290), vec!(
+					pt!(-3,0), pt!(3,0), pt!(3,90),
+					pt!(15,100), pt!(0,115), pt!(-15,100), pt!(-3,90)
+				), 50.0, 160),
+
+				// bobbing propeller on the left
+				Wall::rotating(pt!(50,380), Self::block(0, 0, 30, 4), 0, false, 180)
+					.and(Wall::ycos(vec!(), 30.0, 3)),
+
+				// cart running along the rails
+				Wall::following(Self::block(0, 0, 15, 8), vec!(
+					pt!(40,470), pt!(180,520), pt!(wi-40,470)
+				), true, 300),
+
+				// breathing diamonds
+				Wall::pulsing(pt!(60,580), vec!(pt!(-20,0), pt!(0,-20), pt!(20,0), pt!(0,20)), 0.4, 120),
+				Wall::pulsing(pt!(wi-60,580), vec!(pt!(-20,0), pt!(0,-20), pt!(20,0), pt!(0,20)), 0.4, 120)
+					.with_phase(60),
+
+				// gate over the pad: slides in, turns upright, turns back and leaves
+				Wall::keyframed(pt!(180,590), Self::block(0, 0, 40, 4), vec!(
+					(0, pt!(-130,0), 0.0, 1.0),
+					(80, pt!(-130,0), 0.0, 1.0),
+					(140, pt!(0,0), 0.0, 1.0),
+					(200, pt!(0,0), 90.0, 1.0),
+					(300, pt!(0,0), 90.0, 1.0),
+					(360, pt!(0,0), 0.0, 1.0),
+					(420, pt!(-130,0), 0.0, 1.0),
+				), true),
+			),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
+		}
+	}
+
+	pub fn ferry(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
+		let wi = 360;
+		let w = wi as Fpt;
+		let h = 700 as Fpt;
+		let f = Self::fuel_increased(fuel);
+
+		Self {
+			pos0: Point::new(60.0, 40.0),
+			pads: vec!(Pad::new(150.0, 210.0, 625.0).on_wall(0)),
+			asteroid_area: Point::new(w, 400.0),
+			initial_fuel: f,
+			full_fuel: max(f,750) as Fpt,
+			walls: vec!(
+				// rocking ferry carrying the pad across the chasm
+				Wall::pendulum(pt!(180,640), Self::block(0, 0, 50, 10), 8.0, 200)
+					.and(Wall::oscillating(vec!(), pt!(110,0), 300))
+					.landable(),
+
+				// elevator to rest on halfway down
+				Wall::ycos(Self::block(wi-50, 400, 40, 6), 120.0, 1).landable(),
+
+				wall!( pt!(0,250), pt!(160,250), pt!(170,265), pt!(0,275) ),
+				wall!( pt!(wi,150), pt!(wi,170), pt!(wi-120,165) ),
+			),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
 		}
 	}
 
+	pub fn relay(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
+		let wi = 320;
+		let w = wi as Fpt;
+		let h = 1280 as Fpt;
+
+		Self {
+			viewport: Some(pt!(320, 640)),
+			viewport_pos0: Some(pt!(0,0)),
+			pos0: Point::new(240.0, 50.0),
+			pads: vec!(
+				Pad::new(30.0, 100.0, 600.0).refuel(400.0),
+				Pad::new(220.0, 290.0, 1230.0),
+			),
+			stages: vec!(0, 1),
+			asteroid_pos0: Point::new(0.0, 700.0),
+			asteroid_area: Point::new(w, 450.0),
+			pickups: vec!(
+				Pickup::new(PickupKind::Shield(300), pt!(60,560)).respawning(800),
+				Pickup::new(PickupKind::Gem(300), pt!(20,940)),
+				Pickup::new(PickupKind::Fuel(100.0), pt!(290,1120)),
+			),
+			walls: vec!(
+				// ledge holding the relay pad
+				wall!( pt!(0,605), pt!(130,605), pt!(110,640), pt!(0,660) ),
+				wall!( pt!(wi,300), pt!(wi,340), pt!(140,330) ),
+				xcwall!( pt!(150,850), pt!(wi-40,850), pt!(wi-40,870), pt!(150,870) ),
+				wall!( pt!(0,1000), pt!(170,1020), pt!(0,1040) ),
+				wall!( pt!(200,1280), pt!(210,1235), pt!(300,1235), pt!(310,1280) ),
+			),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
+		}
+	}
+
+	pub fn delivery(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
+		let wi = 320;
+		let w = wi as Fpt;
+		let h = 640 as Fpt;
+		let f = Self::fuel_increased(fuel);
+
+		Self {
+			pos0: Point::new(60.0, 40.0),
+			pads: vec!(Pad::new(20.0, 160.0, 600.0).worth(2.0)),
+			asteroid_pos0: Point::new(0.0, 150.0),
+			asteroid_area: Point::new(w, 300.0),
+			initial_fuel: f,
+			full_fuel: max(f,750) as Fpt,
+			cargo: Some(pt!(270,392)),
+			walls: vec!(
+				// ledge with the pod
+				wall!( pt!(wi,400), pt!(220,400), pt!(230,420), pt!(wi,430) ),
+				wall!( pt!(0,200), pt!(200,220), pt!(0,240) ),
+				wall!( pt!(wi,280), pt!(140,300), pt!(wi,310) ),
+				wall!( pt!(0,605), pt!(wi,605), pt!(wi,h), pt!(0,h) ),
+			),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
+		}
+	}
+
+	pub fn fortress(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
+		let wi = 320;
+		let w = wi as Fpt;
+		let h = 640 as Fpt;
+		let f = Self::fuel_increased(fuel);
+
+		Self {
+			pos0: Point::new(160.0, 40.0),
+			pads: vec!(Pad::new(120.0, 200.0, 600.0).worth(2.0)),
+			asteroid_pos0: Point::new(0.0, 100.0),
+			asteroid_area: Point::new(w, 100.0),
+			initial_fuel: f,
+			full_fuel: max(f,750) as Fpt,
+			pickups: vec!(
+				Pickup::new(PickupKind::Shield(400), pt!(30,170)),
+			),
+			turrets: vec!(
+				Turret::new(pt!(90,260), 90.0).on_wall(0),
+				Turret::new(pt!(230,400), 270.0).on_wall(1).firing_every(90),
+				Turret::new(pt!(40,605), 0.0).on_wall(2).turning(0.8),
+			),
+			mines: vec!(
+				Mine::new(pt!(60,380)),
+				Mine::new(pt!(270,220)),
+				Mine::new(pt!(160,480)).sensing(120.0),
+			),
+			walls: vec!(
+				wall!( pt!(0,220), pt!(90,250), pt!(90,270), pt!(0,300) ),
+				Wall::ycos(vec!( pt!(wi,360), pt!(230,390), pt!(230,410), pt!(wi,440) ), 25.0, 2),
+				wall!( pt!(0,605), pt!(wi,605), pt!(wi,h), pt!(0,h) ),
+			),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
+		}
+	}
+
+	pub fn vault(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
+		let wi = 320;
+		let w = wi as Fpt;
+		let h = 640 as Fpt;
+		let f = Self::fuel_increased(fuel);
+
+		Self {
+			pos0: Point::new(60.0, 40.0),
+			pads: vec!(Pad::new(120.0, 200.0, 600.0).worth(2.0)),
+			asteroid_pos0: Point::new(0.0, 100.0),
+			asteroid_area: Point::new(w, 150.0),
+			initial_fuel: f,
+			full_fuel: max(f,750) as Fpt,
+			switches: vec!(
+				// the corner beacon holds the middle gate off for a while
+				Switch::zone(Self::block(290, 120, 20, 20), 2).for_steps(300),
+				// landing on the ledge opens the vault
+				Switch::landing(4, 1),
+			),
+			lasers: vec!(
+				LaserGate::new(pt!(40,300), pt!(wi-40,300), 60, 90).switched(2),
+				LaserGate::new(pt!(40,420), pt!(wi-40,420), 40, 40).with_phase(40),
+			),
+			walls: vec!(
+				wall!( pt!(0,290), pt!(40,290), pt!(40,310), pt!(0,310) ),
+				wall!( pt!(wi,290), pt!(wi-40,290), pt!(wi-40,310), pt!(wi,310) ),
+				wall!( pt!(0,410), pt!(40,410), pt!(40,430), pt!(0,430) ),
+				wall!( pt!(wi,410), pt!(wi-40,410), pt!(wi-40,430), pt!(wi,430) ),
+				Wall::fixed(Self::block(270, 500, 40, 5)).landable(),
+				// the vault around the pad and its lid
+				Wall::fixed(Self::block(95, 570, 5, 35)),
+				Wall::fixed(Self::block(225, 570, 5, 35)),
+				Wall::fixed(Self::block(160, 530, 70, 5)).door(1, pt!(-140,0)),
+				wall!( pt!(0,605), pt!(wi,605), pt!(wi,h), pt!(0,h) ),
+			),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
+		}
+	}
+
+	pub fn warp(ast: u32, thrust: u32, fuel: u32, gravity: u32, fric: u32, handling: u32) -> Self {
+		let wi = 320;
+		let w = wi as Fpt;
+		let h = 640 as Fpt;
+
+		Self {
+			pos0: Point::new(160.0, 60.0),
+			pads: vec!(Pad::new(130.0, 190.0, 470.0).worth(2.0)),
+			asteroid_pos0: Point::new(0.0, 300.0),
+			asteroid_area: Point::new(w, 100.0),
+			portals: vec!(
+				Portal::new(pt!(50,170), pt!(270,330)),
+				// comes out under the island heading the other way
+				Portal::new(pt!(270,120), pt!(60,560)).sized(22.0).rotating(180.0),
+			),
+			wrap: true,
+			walls: vec!(
+				// splits the level, the way down is through a portal or over the top
+				wall!( pt!(0,230), pt!(wi,230), pt!(wi,250), pt!(0,250) ),
+				// island holding the pad
+				wall!( pt!(110,475), pt!(210,475), pt!(180,510), pt!(140,510) ),
+			),
+			..Self::base(Point::new(w, h), ast, thrust, fuel, gravity, fric, handling)
+		}
+	}
+
+	pub fn with_fuel_mass(mut self, mass: Fpt) -> Self {
+		self.fuel_mass = mass;
+		self
+	}
+
+	pub fn armed(mut self, ammo: u32) -> Self {
+		self.ammo = ammo;
+		self
+	}
+
 	pub fn get_walls(&self, gstep: u32, tr: &Trig) -> Vec<Vec<Point>> {
 		self.walls.iter().map(|wall| wall.shape(gstep, tr)).collect()
 	}
 
+	pub fn is_platform(&self, idx: usize) -> bool {
+		self.walls.get(idx).map(|w| w.platform).unwrap_or(false)
+	}
+
+	pub fn door(&self, idx: usize) -> Option<(u32, Point)> {
+		self.walls.get(idx).and_then(|w| w.door.clone())
+	}
+
+	// position and barrel direction at rest of every turret
+	pub fn get_turrets(&self, gstep: u32, tr: &Trig) -> Vec<(Point, Deg)> {
+		self.turrets.iter().map(|t| {
+			let pos = t.position();
+			match t.wall().and_then(|i| self.walls.get(i)) {
+				None => (pos.clone(), t.facing()),
+				Some(wall) => {
+					let rel = pt!(pos.x() - wall.base.x(), pos.y() - wall.base.y());
+					let tip = pt!(rel.x() + tr.sin(t.facing()), rel.y() - tr.cos(t.facing()));
+					let placed = wall.place(&[rel, tip], gstep, tr);
+					let facing = (placed[1].x() - placed[0].x()).atan2(placed[0].y() - placed[1].y()).to_degrees();
+					(placed[0].clone(), facing)
+				}
+			}
+		}).collect()
+	}
+
+	// corners of every pad, top left first and clockwise
+	pub fn get_pads(&self, gstep: u32, tr: &Trig) -> Vec<Vec<Point>> {
+		self.pads.iter().map(|pad| {
+			let corners = vec!(
+				Point::new(pad.x0, pad.y),
+				Point::new(pad.x1, pad.y),
+				Point::new(pad.x1, pad.y + 5.0),
+				Point::new(pad.x0, pad.y + 5.0)
+			);
+			match pad.wall.and_then(|i| self.walls.get(i)) {
+				None => corners,
+				Some(wall) => {
+					let rel: Vec<Point> = corners.iter().map(|c| pt!(c.x() - wall.base.x(), c.y() - wall.base.y())).collect();
+					wall.place(&rel, gstep, tr)
+				}
+			}
+		}).collect()
+	}
+
 }
 
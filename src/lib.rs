@@ -6,6 +6,7 @@ use crate::view::menuview::MenuView;
 mod common;
 mod rand;
 mod geom;
+mod camera;
 mod levels;
 mod engine;
 mod view;
@@ -1,6 +1,7 @@
 
 use crate::common::Fpt;
 
+#[derive(Clone)]
 pub struct Random {
 	data: Vec<u8>,
 	ptr: usize
@@ -14,6 +15,21 @@ impl Random {
 			data: d
 		}
 	}
+	// reproducible sequence, the same seed gives the same values on every
+	// platform
+	pub fn seeded(seed: u32) -> Self {
+		let mut x = seed | 1;
+		let d = (0..1024).map(|_| {
+			x ^= x << 13;
+			x ^= x >> 17;
+			x ^= x << 5;
+			(x >> 24) as u8
+		}).collect();
+		Self {
+			ptr: 0,
+			data: d
+		}
+	}
 	pub fn next(&mut self) -> u8 {
 		let rv = self.data[self.ptr];
 		self.ptr += 1;
@@ -1,4 +1,6 @@
 
+pub mod controlsview;
 pub mod gameview;
+pub mod input;
 pub mod menuview;
 
@@ -0,0 +1,143 @@
+use wasm_bindgen::prelude::*;
+use web_sys::HtmlElement;
+use std::rc::Rc;
+use std::cell::{Cell,RefCell};
+
+use crate::view::menuview::{MenuView,MenuViewData};
+use crate::view::input::{KeyBindings,Action};
+use crate::common::*;
+use crate::attach;
+
+pub struct ControlsView {
+	root: HtmlElement,
+	bindings: Rc<RefCell<KeyBindings>>,
+	waiting: Rc<Cell<Option<Action>>>,
+	// listens on the whole document, removed when leaving the view
+	keyfn: Rc<Closure<dyn FnMut(web_sys::KeyboardEvent)>>,
+	data: MenuViewData,
+}
+
+impl ControlsView {
+	pub fn new(root: HtmlElement, data: MenuViewData) -> Self {
+		let bindings = data.bindings();
+		let waiting = Rc::new(Cell::new(None));
+		let keyfn = Self::rebind_fn(Rc::clone(&waiting), Rc::clone(&bindings));
+		Self {
+			root,
+			bindings,
+			waiting,
+			keyfn: Rc::new(keyfn),
+			data,
+		}
+	}
+
+	fn item_id(action: Action) -> String {
+		format!("key_{}", action.name())
+	}
+
+	fn label_action(action: Action, bindings: &KeyBindings) -> String {
+		let keys = bindings.keys(action).iter()
+			.map(|k| KeyBindings::key_label(k))
+			.collect::<Vec<String>>();
+		let keys = if keys.is_empty() { "NONE".to_string() } else { keys.join(" ") };
+		format!("<span>{}: {}</span>", action.label(), keys)
+	}
+
+	fn refresh(bindings: &KeyBindings) {
+		for action in Action::ALL.iter() {
+			elem::<HtmlElement>(Self::item_id(*action).as_str())
+				.set_inner_html(Self::label_action(*action, bindings).as_str());
+		}
+	}
+
+	fn setup_html(&self) {
+		let bindings = (*self.bindings).borrow();
+		let items = Action::ALL.iter()
+			.map(|a| format!("<div id=\"{}\" class=\"menuitem\">{}</div>", Self::item_id(*a), Self::label_action(*a, &bindings)))
+			.collect::<Vec<String>>()
+			.join("");
+		self.root.set_inner_html(format!("\
+			<div class=\"menu full center\">\
+				<div class=\"menuheader\">\
+					<div class=\"gametitle\">Controls</div>\
+					<div class=\"subtitle\">\
+						Select an action, then press a key\
+					</div>\
+				</div>\
+				{}\
+				<div class=\"buttons\">\
+					<div id=\"defaults\" class=\"button\">\
+						DEFAULTS\
+					</div>\
+					<div id=\"back\" class=\"button\">\
+						BACK\
+					</div>\
+				</div>\
+			</div>\
+			", items).as_str());
+	}
+
+	fn setup_events(&self, evt: &str) {
+		for action in Action::ALL.iter() {
+			let action = *action;
+			let waiting = Rc::clone(&self.waiting);
+			let bindings = Rc::clone(&self.bindings);
+			attach!(Self::item_id(action).as_str(), evt, move |event: web_sys::Event| {
+				event.prevent_default();
+				Self::refresh(&bindings.borrow());
+				waiting.set(Some(action));
+				elem::<HtmlElement>(Self::item_id(action).as_str())
+					.set_inner_html(format!("<span>{}: PRESS A KEY</span>", action.label()).as_str());
+			});
+		}
+
+		let waiting = Rc::clone(&self.waiting);
+		let bindings = Rc::clone(&self.bindings);
+		attach!("defaults", evt, move |event: web_sys::Event| {
+			event.prevent_default();
+			waiting.set(None);
+			KeyBindings::reset();
+			bindings.replace(KeyBindings::default_bindings());
+			Self::refresh(&bindings.borrow());
+		});
+
+		let waiting = Rc::clone(&self.waiting);
+		let keyfn = Rc::clone(&self.keyfn);
+		let data = self.data.clone();
+		attach!("back", evt, move |event: web_sys::Event| {
+			event.prevent_default();
+			waiting.set(None);
+			let _ = document().remove_event_listener_with_callback("keydown", (*keyfn).as_ref().unchecked_ref());
+			let view = MenuView::new(target_elem(), data.clone());
+			view.show();
+		});
+	}
+
+	fn rebind_fn(waiting: Rc<Cell<Option<Action>>>, bindings: Rc<RefCell<KeyBindings>>) -> Closure<dyn FnMut(web_sys::KeyboardEvent)> {
+		Closure::new(move |event: web_sys::KeyboardEvent| {
+			let action = match waiting.take() {
+				Some(a) => a,
+				None => return
+			};
+			event.prevent_default();
+			let mut bindings = bindings.borrow_mut();
+			// escape cancels unless it is being bound to pause
+			if event.code() != "Escape" || action == Action::Pause {
+				bindings.bind(action, event.code().as_str());
+				bindings.save();
+			}
+			Self::refresh(&bindings);
+		})
+	}
+
+	fn setup_keyboard_events(&self) {
+		document().add_event_listener_with_callback("keydown", (*self.keyfn).as_ref().unchecked_ref()).expect("Cannot attach event");
+	}
+
+	pub fn show(&self) {
+		self.setup_html();
+		self.setup_events("click");
+		self.setup_events("touchstart");
+		self.setup_keyboard_events();
+	}
+}
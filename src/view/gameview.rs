@@ -1,17 +1,18 @@
 use wasm_bindgen::prelude::*;
-use std::cell::RefCell;
+use std::cell::{Cell,RefCell};
 use std::rc::Rc;
 use std::collections::HashMap;
 use web_sys::{HtmlElement,OffscreenCanvas,ImageBitmap};
-use std::cmp::min;
+use std::f64;
 
 use crate::common::*;
-use crate::{attach,pt};
-use crate::geom::Point;
+use crate::{attach,attach_window,pt};
+use crate::geom::{Point,Deg};
 // use crate::dlog;
-use crate::engine::GameEngine;
-use crate::levels::GameData;
+use crate::engine::{GameEngine,PickupKind,Trigger};
+use crate::levels::{GameData,Pad};
 use crate::view::menuview::{MenuView,MenuViewData};
+use crate::view::input::{Input,GamepadSource,Action,Source};
 use crate::rand::Random;
 
 macro_rules! path {
@@ -46,13 +47,22 @@ macro_rules! stroke {
 	}
 }
 
+// pointer id -> (x, y at start, y)
+type Touches = HashMap<i32,(i32,i32,i32)>;
+
+const ENGINE_STEP_MS : u64 = 25u64;
 const GAME_DIV_STYLE: &str = "z-index: 0; background-color: #000;";
+const TRAJECTORY_STEPS: u32 = 80;
+const MINIMAP_WIDTH: f64 = 60.0;
 pub struct GameView {
 	engine: Rc<RefCell<GameEngine>>,
+	input: Rc<RefCell<Input>>,
 	root: HtmlElement,
 	config: MenuViewData,
 	arrow: Rc<ImageBitmap>,
 	background: Rc<ImageBitmap>,
+	vsplit: Rc<Cell<i32>>,
+	resized: Rc<Cell<bool>>,
 }
 
 impl GameView {
@@ -110,14 +120,17 @@ impl GameView {
 
 		Self {
 			engine: Rc::new(RefCell::new(eng)),
+			input: Rc::new(RefCell::new(Input::new())),
 			arrow: Rc::new(arrow),
 			background: Rc::new(bg),
 			config: md,
+			vsplit: Rc::new(Cell::new(0)),
+			resized: Rc::new(Cell::new(false)),
 			root: root
 		}
 	}
 
-	fn draw(canvas: &web_sys::HtmlCanvasElement, engine: &GameEngine, background: &ImageBitmap, arrow: &ImageBitmap, opacity: i32) {
+	fn draw(canvas: &web_sys::HtmlCanvasElement, engine: &GameEngine, config: &MenuViewData, background: &ImageBitmap, arrow: &ImageBitmap, opacity: i32) {
 		let context = canvas
 			.get_context("2d")
 			.unwrap()
@@ -129,9 +142,11 @@ impl GameView {
 			let _ = elem::<HtmlElement>("game").set_attribute("style", format!("opacity: {:.2}; {}", opacity as Fpt / 100.0, GAME_DIV_STYLE).as_str());
 		}
 
-		let cw = canvas.width().into();
-		let ch = canvas.height().into();
-		let _ = context.reset_transform();
+		// logical size, the backing store may be larger on HiDPI screens
+		let cw = engine.viewport_width();
+		let ch = engine.viewport_height();
+		let scale = canvas.width() as f64 / cw;
+		let _ = context.set_transform(scale, 0.0, 0.0, scale, 0.0, 0.0);
 		// context.set_fill_style(&JsValue::from_str("#000000"));
 		// context.fill_rect(0.0, 0.0, cw, ch);
 		let pat = context.create_pattern_with_image_bitmap(background, "repeat").unwrap().unwrap();
@@ -140,75 +155,105 @@ impl GameView {
 			let vpos = engine.viewport_pos();
 			let vx = vpos.x();
 			let vy = vpos.y();
+			let vw = engine.visible_width();
+			let vh = engine.visible_height();
 
-			let _ = context.translate(-vx, -vy);
+			Self::apply_camera(&context, engine, scale);
 			context.set_fill_style(&pat);
-			context.fill_rect(vx, vy, cw, ch);
+			context.fill_rect(vx, vy, vw, vh);
 
 			if vx < 0.0 {
-				shape!(context, "#a83e3e", vec![pt!(vx,vy), pt!(0,vy), pt!(0,ch+vy), pt!(vx,ch+vy)]);
+				shape!(context, "#a83e3e", vec![pt!(vx,vy), pt!(0,vy), pt!(0,vh+vy), pt!(vx,vh+vy)]);
 			}
 			if vy < 0.0 {
-				shape!(context, "#a83e3e", vec![pt!(vx,vy), pt!(vx,0), pt!(cw+vx,0), pt!(cw+vx,vy)]);
+				shape!(context, "#a83e3e", vec![pt!(vx,vy), pt!(vx,0), pt!(vw+vx,0), pt!(vw+vx,vy)]);
 			}
-			let out = vx + cw - engine.area_width();
+			let out = vx + vw - engine.area_width();
 			if out > 0.0 {
-				shape!(context, "#a83e3e", vec![pt!(cw-out+vx,vy), pt!(cw+vx,vy), pt!(cw+vx,ch+vy), pt!(cw-out+vx,ch+vy)]);
+				shape!(context, "#a83e3e", vec![pt!(vw-out+vx,vy), pt!(vw+vx,vy), pt!(vw+vx,vh+vy), pt!(vw-out+vx,vh+vy)]);
 			}
-			let out = vy + ch - engine.area_height();
+			let out = vy + vh - engine.area_height();
 			if out > 0.0 {
-				shape!(context, "#a83e3e", vec![pt!(vx,ch-out+vy), pt!(vx,ch+vy), pt!(cw+vx,ch+vy), pt!(cw+vx,ch-out+vy)]);
+				shape!(context, "#a83e3e", vec![pt!(vx,vh-out+vy), pt!(vx,vh+vy), pt!(vw+vx,vh+vy), pt!(vw+vx,vh-out+vy)]);
 			}
 		} else {
 			context.set_fill_style(&pat);
 			context.fill_rect(0.0, 0.0, cw, ch);
 		}
 
-		let wind_step = engine.get_wind_step();
 		engine.iter_winds(|w,trig| {
 			context.save();
 			stroke!(context, "#12fff7", w.shape());
 			path!(context, w.shape());
 			let pat = context.create_pattern_with_image_bitmap(arrow, "repeat").unwrap().unwrap();
 			let dir = w.direction();
+			let phase = w.phase();
 			let _ = context.rotate(trig.rad(dir));
-			if wind_step > 0 {
-				let _ = context.translate(10.0, 0.0);
-			}
+			let _ = context.translate(phase, 0.0);
 			let shp : Vec<Point> = w.shape().iter().map(|p| {
 				let mut p = trig.rot(p, -dir);
-				if wind_step > 0 {
-					p.add(&pt!(-10.0,0.0));
-				}
+				p.add(&pt!(-phase,0.0));
 				p
 			}).collect();
 
+			// arrows fade out as the wind calms down
+			context.set_global_alpha(Fpt::max(0.15, Fpt::min(1.0, w.strength())));
 			context.set_fill_style(&pat);
 			path!(context, shp);
 			context.fill();
 			context.restore();
 		});
 
+		Self::draw_wells(&context, engine);
 		for o in engine.obs_shape().iter() {
 			shape!(context, "#a83e3e", o);
 		}
 		for o in engine.aster_shape().iter() {
 			shape!(context, "#b88b2c", o);
 		}
+		Self::draw_pickups(&context, engine);
+		Self::draw_cargo(&context, engine);
+		Self::draw_hostiles(&context, engine);
+		Self::draw_logic(&context, engine);
+		Self::draw_portals(&context, engine);
+		context.set_fill_style(&JsValue::from_str("#fff"));
+		engine.iter_projectiles(|p| {
+			let pos = p.position();
+			context.fill_rect(pos.x()-1.5, pos.y()-1.5, 3.0, 3.0);
+		});
+		if config.trajectory() {
+			Self::draw_trajectory(&context, engine);
+		}
 		let mut ship_col = "#42a4f5";
 		if engine.block_alert() {
 			ship_col = "#e05f38";
 		}
 		shape!(context, ship_col, engine.ship_shape());
-		shape!(context, "#8fffc3", engine.land_shape());
+		// blinks when about to run out
+		let shield = engine.shield();
+		if shield > 0 && (shield > 80 || shield % 10 < 5) {
+			let pos = engine.ship_pos();
+			context.set_stroke_style(&JsValue::from_str("#66ccff"));
+			context.begin_path();
+			let _ = context.arc(pos.x(), pos.y(), 24.0, 0.0, 2.0 * f64::consts::PI);
+			context.stroke();
+		}
+		Self::draw_pads(&context, engine);
 		engine.iter_part(|p| {
 			context.set_global_alpha(p.alpha());
-			context.set_fill_style(p.color());
+			context.set_fill_style(&JsValue::from_str(p.color()));
 			let pos = p.position();
 			context.fill_rect(pos.x()-1.0, pos.y()-1.0, 2.0, 2.0);
 		});
 		context.set_global_alpha(1.0);
 
+		// fluids are drawn over the ship so it looks submerged
+		context.set_global_alpha(0.35);
+		engine.iter_fluids(|z| {
+			shape!(context, "#1e5aa0", z.shape());
+		});
+		context.set_global_alpha(1.0);
+
 		/*
 		let areaw = engine.viewport_width();
 		let areah = engine.viewport_height();
@@ -276,6 +321,17 @@ impl GameView {
 		context.close_path();
 		context.fill();
 
+		// throttle, a thin bar just over the fuel gauge
+		if engine.throttle() > 0.0 {
+			context.set_fill_style(&JsValue::from_str("#fa0"));
+			context.fill_rect(hud_x0, hud_y0 - hud_tick_size - 3.0, hud_width * engine.throttle(), 2.0);
+		}
+		// RCS monoprop, over the throttle
+		if engine.has_inertia() {
+			context.set_fill_style(&JsValue::from_str("#ddd"));
+			context.fill_rect(hud_x0, hud_y0 - hud_tick_size - 6.0, engine.rcs_sz(hud_width), 2.0);
+		}
+
 		context.set_stroke_style(&JsValue::from_str("#fff"));
 		context.begin_path();
 		context.move_to(hud_x0, hud_y1);
@@ -291,24 +347,456 @@ impl GameView {
 		}
 		context.stroke();
 
+		if config.telemetry() {
+			Self::draw_telemetry(&context, engine, scale);
+		}
+		if engine.scrollable() {
+			Self::draw_minimap(&context, engine, scale);
+			Self::draw_pad_pointer(&context, engine, scale);
+		}
+		if engine.is_mission() {
+			Self::draw_mission(&context, engine, scale);
+		}
+		if engine.has_weapons() {
+			Self::draw_ammo(&context, engine, scale);
+		}
+		if let Some(score) = engine.score() {
+			Self::draw_score(&context, engine, scale, format!("SCORE {}", score).as_str());
+		} else if engine.bonus() > 0 {
+			Self::draw_score(&context, engine, scale, format!("+{}", engine.bonus()).as_str());
+		}
+
 		//context.translate(self.engine.pos.x(), gd.pos.y());
 		//context.rotate(self.engine.trig.rad(gd.rot));
 		//let _ = context.reset_transform();
 	}
 
-	fn setup_html(&self) {
-		let swidth = window().inner_width().unwrap().as_f64().unwrap() as u32 - 6;
-		let sheight = window().inner_height().unwrap().as_f64().unwrap() as u32 - 6;
-
-		let eng = (*self.engine).borrow();
-		let engw = eng.viewport_width();
-		let engh = eng.viewport_height();
-		let widthstep: u32 = (engw / 10.0) as u32;
-		let heightstep: u32 = (engh / 10.0) as u32;
-		let ratio = min(swidth / widthstep, sheight / heightstep);
-		let cw = widthstep * ratio;
-		let ch = heightstep * ratio;
+	fn apply_camera(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine, scale: f64) {
+		let vpos = engine.viewport_pos();
+		let zoom = engine.viewport_zoom() * scale;
+		let _ = context.set_transform(zoom, 0.0, 0.0, zoom, -vpos.x() * zoom, -vpos.y() * zoom);
+	}
+
+	fn draw_trajectory(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine) {
+		let traj = engine.predict(TRAJECTORY_STEPS);
+		let len = traj.path().len() as f64;
+
+		context.set_fill_style(&JsValue::from_str("#ffffff"));
+		for (i, p) in traj.path().iter().enumerate().step_by(3) {
+			context.set_global_alpha(1.0 - 0.7 * (i as f64) / len);
+			context.fill_rect(p.x()-1.0, p.y()-1.0, 2.0, 2.0);
+		}
+		context.set_global_alpha(1.0);
+
+		if let Some(p) = traj.impact() {
+			if traj.landed() {
+				context.set_stroke_style(&JsValue::from_str("#8fffc3"));
+				context.begin_path();
+				let _ = context.arc(p.x(), p.y(), 6.0, 0.0, f64::consts::PI * 2.0);
+				context.stroke();
+			} else {
+				context.set_stroke_style(&JsValue::from_str("#f00"));
+				context.begin_path();
+				context.move_to(p.x()-5.0, p.y()-5.0);
+				context.line_to(p.x()+5.0, p.y()+5.0);
+				context.move_to(p.x()+5.0, p.y()-5.0);
+				context.line_to(p.x()-5.0, p.y()+5.0);
+				context.stroke();
+			}
+		}
+	}
+
+	fn telemetry_col(v: Fpt, limit: Fpt) -> &'static str {
+		if v.abs() < limit * 0.6 {
+			"#0f0"
+		} else if v.abs() < limit {
+			"#ff0"
+		} else {
+			"#f00"
+		}
+	}
+
+	// faint rings where the pull of each gravity well halves
+	fn draw_wells(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine) {
+		context.save();
+		context.set_stroke_style(&JsValue::from_str("#b37cff"));
+		context.set_line_width(1.0);
+		engine.iter_wells(|w| {
+			let pos = w.position();
+			let mut accel = w.strength();
+			for i in 1..5 {
+				accel /= 2.0;
+				context.set_global_alpha(0.3 / i as f64);
+				context.begin_path();
+				let _ = context.arc(pos.x(), pos.y(), w.reach(accel), 0.0, 2.0 * f64::consts::PI);
+				context.stroke();
+			}
+		});
+		context.restore();
+	}
+
+	fn draw_telemetry(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine, scale: f64) {
+		let speed = engine.ship_speed();
+		let att = engine.attitude();
+		let fuel = engine.fuel_sz(100.0);
+		let mut rows = vec![
+			("VS", format!("{:+.2}", speed.y()), Self::telemetry_col(speed.y(), engine.levelling_speed_y())),
+			("HS", format!("{:+.2}", speed.x()), Self::telemetry_col(speed.x(), engine.levelling_speed_x())),
+			("ATT", format!("{:+.0}", att), Self::telemetry_col(att, engine.levelling_rot() as Fpt)),
+			("ALT", format!("{:.0}", engine.pad_altitude()), "#fff"),
+			("DST", format!("{:.0}", engine.pad_distance()), "#fff"),
+			("FUEL", format!("{:.0}%", fuel), if engine.fuel_warn() { "#f00" } else { "#0f0" }),
+			("THR", format!("{:.0}%", engine.throttle() * 100.0), "#fa0"),
+		];
+		if engine.has_inertia() {
+			rows.push(("ROT", format!("{:+.1}", engine.rot_speed()), "#fff"));
+			rows.push(("RCS", format!("{:.0}%", engine.rcs_sz(100.0)), if engine.rcs_sz(100.0) <= 20.0 { "#f00" } else { "#ddd" }));
+		}
+		if engine.has_mass() {
+			rows.push(("MASS", format!("{:.2}", engine.mass()), "#fff"));
+		}
+
+		context.save();
+		let _ = context.set_transform(scale, 0.0, 0.0, scale, 0.0, 0.0);
+		context.set_global_alpha(0.6);
+		context.set_fill_style(&JsValue::from_str("#000"));
+		context.fill_rect(4.0, 4.0, 78.0, 8.0 + 10.0 * rows.len() as f64);
+		context.set_global_alpha(1.0);
+		context.set_font("8px f8bit");
+		context.set_text_baseline("top");
+		for (i, (label, value, col)) in rows.iter().enumerate() {
+			let y = 8.0 + 10.0 * i as f64;
+			context.set_fill_style(&JsValue::from_str("#fff"));
+			let _ = context.fill_text(label, 8.0, y);
+			context.set_fill_style(&JsValue::from_str(col));
+			let _ = context.fill_text(value, 40.0, y);
+		}
+		context.restore();
+	}
+
+	fn draw_minimap(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine, scale: f64) {
+		let mscale = MINIMAP_WIDTH / engine.area_width();
+		let mw = MINIMAP_WIDTH;
+		let mh = engine.area_height() * mscale;
+		let mx = engine.viewport_width() - mw - 6.0;
+		let my = 6.0;
+
+		context.save();
+		let _ = context.set_transform(scale, 0.0, 0.0, scale, 0.0, 0.0);
+		context.set_global_alpha(0.6);
+		context.set_fill_style(&JsValue::from_str("#000"));
+		context.fill_rect(mx, my, mw, mh);
+		context.set_global_alpha(1.0);
+
+		let _ = context.translate(mx, my);
+		let _ = context.scale(mscale, mscale);
+		context.set_line_width(1.0 / mscale);
+		engine.iter_winds(|w,_| {
+			stroke!(context, "#12fff7", w.shape());
+		});
+		for o in engine.obs_shape().iter() {
+			shape!(context, "#a83e3e", o);
+		}
+		for o in engine.aster_shape().iter() {
+			shape!(context, "#b88b2c", o);
+		}
+		engine.iter_pads(|shape, pad, stock, _| {
+			let col = Self::pad_color(pad, stock);
+			shape!(context, col, shape);
+			// small pads would vanish at this scale
+			let (x, y) = (shape[0].x() / 2.0 + shape[1].x() / 2.0, shape[0].y());
+			context.set_fill_style(&JsValue::from_str(col));
+			context.fill_rect(x - 3.0 / mscale, y - 1.0 / mscale, 6.0 / mscale, 3.0 / mscale);
+		});
+
+		let pos = engine.ship_pos();
+		context.set_fill_style(&JsValue::from_str("#42a4f5"));
+		context.fill_rect(pos.x() - 2.0 / mscale, pos.y() - 2.0 / mscale, 4.0 / mscale, 4.0 / mscale);
+
+		let vpos = engine.viewport_pos();
+		context.set_stroke_style(&JsValue::from_str("#fff"));
+		context.stroke_rect(vpos.x(), vpos.y(), engine.visible_width(), engine.visible_height());
+
+		let _ = context.set_transform(scale, 0.0, 0.0, scale, 0.0, 0.0);
+		context.set_line_width(1.0);
+		context.stroke_rect(mx, my, mw, mh);
+		context.restore();
+	}
+
+	fn pad_color(pad: &Pad, stock: Fpt) -> &'static str {
+		if !pad.is_refuel() {
+			"#8fffc3"
+		} else if stock > 0.0 {
+			"#ffd23f"
+		} else {
+			"#7a6a2a"
+		}
+	}
+
+	fn draw_pads(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine) {
+		context.set_font("8px f8bit");
+		context.set_text_align("center");
+		context.set_text_baseline("bottom");
+		engine.iter_pads(|shape, pad, stock, stage| {
+			let col = Self::pad_color(pad, stock);
+			shape!(context, col, shape);
+			let label = if let Some(n) = stage {
+				if n < engine.stage() {
+					format!("#{} OK", n + 1)
+				} else {
+					format!("#{}", n + 1)
+				}
+			} else if pad.is_refuel() {
+				format!("FUEL {:.0}", stock)
+			} else if pad.multiplier() != 1.0 {
+				format!("x{}", pad.multiplier())
+			} else {
+				return;
+			};
+			context.set_fill_style(&JsValue::from_str(col));
+			let _ = context.fill_text(label.as_str(), (shape[0].x() + shape[1].x()) / 2.0, Fpt::min(shape[0].y(), shape[1].y()) - 3.0);
+		});
+		context.set_text_align("start");
+		context.set_text_baseline("alphabetic");
+	}
+
+	fn draw_mission(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine, scale: f64) {
+		let ms = engine.mission_steps() as u64 * ENGINE_STEP_MS;
+		let stage = usize::min(engine.stage() + 1, engine.stage_count());
+		context.save();
+		let _ = context.set_transform(scale, 0.0, 0.0, scale, 0.0, 0.0);
+		context.set_fill_style(&JsValue::from_str("#fff"));
+		context.set_font("8px f8bit");
+		context.set_text_align("right");
+		context.set_text_baseline("top");
+		let x = engine.viewport_width() - 8.0;
+		// under the minimap when there is one
+		let y = if engine.scrollable() { 14.0 + engine.area_height() * MINIMAP_WIDTH / engine.area_width() } else { 8.0 };
+		let _ = context.fill_text(format!("STAGE {}/{}", stage, engine.stage_count()).as_str(), x, y);
+		let _ = context.fill_text(format!("{}:{:02}.{}", ms / 60000, (ms / 1000) % 60, (ms / 100) % 10).as_str(), x, y + 10.0);
+		context.restore();
+	}
+
+	fn draw_ammo(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine, scale: f64) {
+		context.save();
+		let _ = context.set_transform(scale, 0.0, 0.0, scale, 0.0, 0.0);
+		context.set_fill_style(&JsValue::from_str(if engine.ammo() > 0 { "#fff" } else { "#f00" }));
+		context.set_font("8px f8bit");
+		context.set_text_align("left");
+		context.set_text_baseline("bottom");
+		let _ = context.fill_text(format!("AMMO {}", engine.ammo()).as_str(), 8.0, engine.viewport_height() - 8.0);
+		context.restore();
+	}
+
+	fn draw_pickups(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine) {
+		engine.iter_pickups(|p| {
+			if !p.available() {
+				return;
+			}
+			let col = match p.kind() {
+				PickupKind::Fuel(_) => "#ffd23f",
+				PickupKind::Shield(_) => "#66ccff",
+				PickupKind::Gem(_) => "#ff66ff",
+			};
+			shape!(context, col, p.shape());
+		});
+	}
 
+	fn draw_hostiles(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine) {
+		engine.iter_turrets(|t, pos, dir| {
+			let col = if t.is_alive() { "#e04848" } else { "#666" };
+			context.set_fill_style(&JsValue::from_str(col));
+			context.set_stroke_style(&JsValue::from_str(col));
+			context.begin_path();
+			let _ = context.arc(pos.x(), pos.y(), 6.0, 0.0, 2.0 * f64::consts::PI);
+			context.fill();
+			if t.is_alive() {
+				let rad = dir.to_radians();
+				context.set_line_width(3.0);
+				context.begin_path();
+				context.move_to(pos.x(), pos.y());
+				context.line_to(pos.x() + 12.0 * rad.sin(), pos.y() - 12.0 * rad.cos());
+				context.stroke();
+				context.set_line_width(1.0);
+			}
+		});
+		engine.iter_mines(|m| {
+			if m.is_alive() {
+				shape!(context, "#ff5030", m.shape());
+			}
+		});
+		context.set_fill_style(&JsValue::from_str("#ff8060"));
+		engine.iter_bullets(|b| {
+			let pos = b.position();
+			context.fill_rect(pos.x()-1.5, pos.y()-1.5, 3.0, 3.0);
+		});
+	}
+
+	fn draw_logic(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine) {
+		engine.iter_switches(|s| {
+			let col = if s.is_on() { "#5f5" } else { "#ff0" };
+			match s.trigger() {
+				Trigger::Zone(shape) => {
+					context.save();
+					let _ = context.set_line_dash(&JsValue::from(js_sys::Array::of2(&4.0.into(), &4.0.into())));
+					stroke!(context, col, shape);
+					context.restore();
+				},
+				Trigger::Landing(wall) => {
+					// a light in the middle of the platform
+					if let Some(shape) = engine.obs_shape().get(*wall) {
+						context.set_fill_style(&JsValue::from_str(col));
+						context.fill_rect((shape[0].x() + shape[1].x()) / 2.0 - 3.0, (shape[0].y() + shape[1].y()) / 2.0 - 2.0, 6.0, 2.0);
+					}
+				},
+			}
+		});
+		engine.iter_lasers(|l, on| {
+			let (a, b) = l.ends();
+			context.save();
+			context.set_global_alpha(if on { 1.0 } else { 0.2 });
+			context.set_stroke_style(&JsValue::from_str("#ff2020"));
+			context.set_line_width(if on { 2.0 } else { 1.0 });
+			context.begin_path();
+			context.move_to(a.x(), a.y());
+			context.line_to(b.x(), b.y());
+			context.stroke();
+			context.restore();
+			context.set_fill_style(&JsValue::from_str("#888"));
+			context.fill_rect(a.x() - 3.0, a.y() - 3.0, 6.0, 6.0);
+			context.fill_rect(b.x() - 3.0, b.y() - 3.0, 6.0, 6.0);
+		});
+	}
+
+	fn draw_portals(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine) {
+		const COLORS: [&str; 3] = ["#c080ff", "#40e0d0", "#ffa040"];
+		context.save();
+		engine.iter_portals(|i, p| {
+			let (a, b) = p.ends();
+			context.set_stroke_style(&JsValue::from_str(COLORS[i % COLORS.len()]));
+			for end in [a, b].iter() {
+				for (r, alpha) in [(1.0, 1.0), (0.6, 0.5), (0.3, 0.25)].iter() {
+					context.set_global_alpha(*alpha);
+					context.begin_path();
+					let _ = context.arc(end.x(), end.y(), p.radius() * r, 0.0, 2.0 * f64::consts::PI);
+					context.stroke();
+				}
+			}
+		});
+		// dashed edges where the level wraps around
+		if engine.wraps() {
+			context.set_global_alpha(0.4);
+			context.set_stroke_style(&JsValue::from_str("#c080ff"));
+			let _ = context.set_line_dash(&JsValue::from(js_sys::Array::of2(&6.0.into(), &6.0.into())));
+			context.stroke_rect(0.5, 0.5, engine.area_width() - 1.0, engine.area_height() - 1.0);
+		}
+		context.restore();
+	}
+
+	fn draw_cargo(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine) {
+		let pod = match engine.cargo() {
+			Some(c) => c,
+			None => return
+		};
+		let latch = pod.latching();
+		if !engine.has_collided() && (pod.is_towed() || latch > 0.0) {
+			// the rope shows up faintly while hooking the pod
+			let anchor = engine.remap_ship(&pt!(0,10));
+			// towards the pod, over the edge when the level wraps
+			let mut pos = anchor.clone();
+			pos.add(&engine.rope().unwrap_or(pt!(0,0)));
+			context.save();
+			context.set_global_alpha(if pod.is_towed() { 1.0 } else { 0.2 + 0.5 * latch });
+			context.set_stroke_style(&JsValue::from_str("#c8c8c8"));
+			context.begin_path();
+			context.move_to(anchor.x(), anchor.y());
+			context.line_to(pos.x(), pos.y());
+			context.stroke();
+			context.restore();
+		}
+		shape!(context, "#d9a441", pod.shape());
+	}
+
+	fn draw_score(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine, scale: f64, text: &str) {
+		context.save();
+		let _ = context.set_transform(scale, 0.0, 0.0, scale, 0.0, 0.0);
+		context.set_fill_style(&JsValue::from_str("#8fffc3"));
+		context.set_font("16px f8bit");
+		context.set_text_align("center");
+		context.set_text_baseline("top");
+		let _ = context.fill_text(text, engine.viewport_width() / 2.0, 20.0);
+		context.restore();
+	}
+
+	fn draw_pad_pointer(context: &web_sys::CanvasRenderingContext2d, engine: &GameEngine, scale: f64) {
+		const MARGIN: f64 = 15.0;
+		let vpos = engine.viewport_pos();
+		let zoom = engine.viewport_zoom();
+		let hw = engine.viewport_width() / 2.0;
+		let hh = engine.viewport_height() / 2.0;
+		let pad = engine.pad_center();
+		let dx = (pad.x() - vpos.x()) * zoom - hw;
+		let dy = (pad.y() - vpos.y()) * zoom - hh;
+		if dx.abs() < hw && dy.abs() < hh {
+			return;
+		}
+
+		// clip the direction to the viewport edge
+		let t = f64::min(
+			if dx.abs() > 0.0 { (hw - MARGIN) / dx.abs() } else { f64::MAX },
+			if dy.abs() > 0.0 { (hh - MARGIN) / dy.abs() } else { f64::MAX }
+		);
+
+		context.save();
+		let _ = context.set_transform(scale, 0.0, 0.0, scale, 0.0, 0.0);
+		let _ = context.translate(hw + dx * t, hh + dy * t);
+		let _ = context.rotate(dy.atan2(dx));
+		shape!(context, "#8fffc3", vec![pt!(8,0), pt!(-6,-6), pt!(-6,6)]);
+		context.restore();
+	}
+
+	fn draw_paused(canvas: &web_sys::HtmlCanvasElement, engine: &GameEngine) {
+		let context = canvas
+			.get_context("2d")
+			.unwrap()
+			.unwrap()
+			.dyn_into::<web_sys::CanvasRenderingContext2d>()
+			.unwrap();
+		let cw = engine.viewport_width();
+		let ch = engine.viewport_height();
+		let scale = canvas.width() as f64 / cw;
+		let _ = context.set_transform(scale, 0.0, 0.0, scale, 0.0, 0.0);
+		context.set_global_alpha(0.5);
+		context.set_fill_style(&JsValue::from_str("#000"));
+		context.fill_rect(0.0, 0.0, cw, ch);
+		context.set_global_alpha(1.0);
+		context.set_fill_style(&JsValue::from_str("#fff"));
+		context.set_font("16px f8bit");
+		context.set_text_align("center");
+		context.set_text_baseline("middle");
+		let _ = context.fill_text("PAUSED", cw / 2.0, ch / 2.0);
+		context.set_text_align("start");
+	}
+
+	fn layout(canvas: &web_sys::HtmlCanvasElement, engine: &GameEngine, vsplit: &Cell<i32>) {
+		let win = window();
+		let swidth = win.inner_width().unwrap().as_f64().unwrap();
+		let sheight = win.inner_height().unwrap().as_f64().unwrap();
+		let dpr = f64::max(win.device_pixel_ratio(), 1.0);
+
+		let engw = engine.viewport_width();
+		let engh = engine.viewport_height();
+		let ratio = f64::min((swidth - 6.0) / engw, (sheight - 6.0) / engh);
+		let cw = (engw * ratio).floor();
+		let ch = (engh * ratio).floor();
+
+		let _ = canvas.set_attribute("style", format!("width: {}px; height: {}px; border: 3px solid #fff;", cw, ch).as_str());
+		canvas.set_width((cw * dpr).round() as u32);
+		canvas.set_height((ch * dpr).round() as u32);
+		vsplit.set((swidth / 2.0) as i32);
+	}
+
+	fn setup_html(&self) {
 		self.root.set_inner_html(format!("\
 			<div id=\"console\" class=\"log\" \
 				style=\"\
@@ -324,49 +812,33 @@ impl GameView {
 			</div>\
 			<div id=\"game\" class=\"full center\" \
 				style=\"{}\">\
-			<canvas id=\"canvas\" \
-				style=\"width: {}px; height: {}px; border: 3px solid #fff;\" \
-				width=\"{}\" height=\"{}\"/>\
-			</div>", GAME_DIV_STYLE, cw, ch, engw as u32, engh as u32).as_str());
+			<canvas id=\"canvas\"/>\
+			</div>", GAME_DIV_STYLE).as_str());
+
+		Self::layout(&canvas(), &(*self.engine).borrow(), &self.vsplit);
+	}
+
+	fn setup_resize_events(&self) {
+		let resized = Rc::clone(&self.resized);
+		let resizefn = move |_: web_sys::Event| {
+			resized.set(true);
+		};
+
+		attach_window!("resize", resizefn.clone());
+		attach_window!("orientationchange", resizefn);
 	}
 
 	fn setup_keyboard_events(&self) {
-		let engref = Rc::clone(&self.engine);
+		let inref = Rc::clone(&self.input);
+		let bindings = self.config.bindings();
 		let keyfn = move |event: web_sys::KeyboardEvent| {
-			let mut engine = (*engref).borrow_mut();
-			let kc = event.key_code();
-			let mut lrot: Option<bool> = None;
-			let mut rrot: Option<bool> = None;
-
-			// dlog!(format!("{} {}", event.type_().as_str(), event.key_code()).as_str());
-			match event.type_().as_str() {
-				"keydown" => {
-					if kc == web_sys::KeyEvent::DOM_VK_UP || kc == web_sys::KeyEvent::DOM_VK_SPACE {
-						event.prevent_default();
-						engine.set_thrust(true);
-					} else if kc == web_sys::KeyEvent::DOM_VK_LEFT {
-						event.prevent_default();
-						lrot = Some(true);
-					} else if kc == web_sys::KeyEvent::DOM_VK_RIGHT {
-						event.prevent_default();
-						rrot = Some(true);
-					}
-				},
-				"keyup" => {
-					if kc == web_sys::KeyEvent::DOM_VK_UP || kc == web_sys::KeyEvent::DOM_VK_SPACE {
-						event.prevent_default();
-						engine.set_thrust(false);
-					} else if kc == web_sys::KeyEvent::DOM_VK_LEFT {
-						event.prevent_default();
-						lrot = Some(false);
-					} else if kc == web_sys::KeyEvent::DOM_VK_RIGHT {
-						event.prevent_default();
-						rrot = Some(false);
-					}
-				},
-				_ => {}
+			let mut input = (*inref).borrow_mut();
+
+			// dlog!(format!("{} {}", event.type_().as_str(), event.code()).as_str());
+			if let Some(action) = bindings.borrow().action(&event.code()) {
+				event.prevent_default();
+				input.set(Source::Keyboard, action, event.type_() == "keydown");
 			}
-			engine.set_rotation(lrot, rrot);
 		};
 
 		attach!("keydown", keyfn.clone());
@@ -377,11 +849,11 @@ impl GameView {
 	fn setup_mouse_events(&self) {
 		elem::<HtmlElement>("ctrl").request_pointer_lock();
 		// let _ = js_sys::eval("document.getElementById('ctrl').requestPointerLock({unadjustedMovement: true})");
-		let engref = Rc::clone(&self.engine);
+		let inref = Rc::clone(&self.input);
 		let mousefn = move |event: web_sys::MouseEvent| {
 			event.prevent_default();
 			event.stop_propagation();
-			let mut engine = (*engref).borrow_mut();
+			let mut input = (*inref).borrow_mut();
 			let evt = event.type_();
 			// dlog!(format!("{} {} {}", evt.as_str(), event.movement_x(), event.movement_y()).as_str());
 			match evt.as_str() {
@@ -390,13 +862,13 @@ impl GameView {
 					let my = event.movement_y();
 					let delta = if i32::abs(mx) > i32::abs(my) { mx } else { my };
 					let delta = if i32::abs(delta) <= 20 { delta } else if delta < 0 { -20 } else { 20 };
-					engine.rotate(delta);
+					input.rotate_by(delta as Deg);
 				},
 				"mousedown" => {
-					engine.set_thrust(true);
+					input.set(Source::Pointer, Action::Thrust, true);
 				},
 				"mouseup" => {
-					engine.set_thrust(false);
+					input.set(Source::Pointer, Action::Thrust, false);
 				},
 				_ => {}
 			}
@@ -407,13 +879,22 @@ impl GameView {
 		attach!("ctrl", "mouseup", mousefn);
 	}
 
+	// Thrust touches work as a slider: full throttle where the finger
+	// went down, dragging it down by TOUCH_SLIDER px cuts the engine.
+	fn touch_throttle(touches: &Touches, vsplit: i32) -> Fpt {
+		const TOUCH_SLIDER: Fpt = 120.0;
+		touches.values()
+			.filter(|(x,_,_)| *x < vsplit)
+			.map(|(_,y0,y)| 1.0 - Fpt::max(0.0, (y - y0) as Fpt) / TOUCH_SLIDER)
+			.fold(0.0, Fpt::max)
+	}
+
 	fn setup_pointer_events(&self) {
 		elem::<HtmlElement>("ctrl").request_pointer_lock();
 
-		let swidth = window().inner_width().unwrap().as_f64().unwrap() as u32;
-		let touches : Rc<RefCell<HashMap<i32,i32>>> = Rc::new(RefCell::new(HashMap::new()));
-
-		let vsplit = (swidth / 2) as i32;
+		let touches : Rc<RefCell<Touches>> = Rc::new(RefCell::new(HashMap::new()));
+		let mouse_throttle : Rc<Cell<Fpt>> = Rc::new(Cell::new(1.0));
+		let mouse_down : Rc<Cell<bool>> = Rc::new(Cell::new(false));
 
 		let mut ptcache : Option<bool> = None;
 		let mut is_touch = move |ptype: &String| {
@@ -427,6 +908,7 @@ impl GameView {
 			let evt = event.type_();
 			let id = event.pointer_id();
 			let x = event.page_x();
+			let y = event.page_y();
 			let ptype = event.pointer_type();
 			// dlog!(format!("{} {} {} {:.2} {:.2}", event.pointer_type(), evt.as_str(), event.pointer_id(), event.page_x(), event.page_y()).as_str());
 			/* event.get_coalesced_events().for_each(&mut |jev, ix, ar| {
@@ -435,18 +917,19 @@ impl GameView {
 				dlog!(format!("{} - {} {} {} {} {}", ix, evt.as_str(), ce.pointer_id(), ce.page_x(), ce.movement_x(), ce.movement_y()).as_str());
 			}); */
 
-			(evt,id,x,ptype)
+			(evt,id,x,y,ptype)
 		};
 
-		let engref = Rc::clone(&self.engine);
+		let inref = Rc::clone(&self.input);
 		let touchref = Rc::clone(&touches);
+		let vsplit = Rc::clone(&self.vsplit);
 		let ptrmovefn = move |event: web_sys::PointerEvent| {
-			let (_,id,x,pt) = open_pointer_fn(&event);
+			let (_,id,x,y,pt) = open_pointer_fn(&event);
 			let mut touches = (*touchref).borrow_mut();
-			let mut engine = (*engref).borrow_mut();
+			let mut input = (*inref).borrow_mut();
 			let mut delta : i32 = 0;
 			let touch = is_touch(&pt);
-			if x >= vsplit {
+			if x >= vsplit.get() {
 				if touch {
 					delta += event.movement_y();
 				} else {
@@ -457,53 +940,78 @@ impl GameView {
 				}
 			}
 			if delta != 0 {
-				engine.rotate(delta);
+				input.rotate_by(delta as Deg);
 			}
 			if touch {
-				touches.insert(id, x);
-				engine.set_thrust(touches.values().any(|e| { e < &vsplit }));
+				let y0 = touches.get(&id).map(|t| t.1).unwrap_or(y);
+				touches.insert(id, (x, y0, y));
+				input.set_throttle(Source::Pointer, Self::touch_throttle(&touches, vsplit.get()));
 			}
 		};
 
 		let engref = Rc::clone(&self.engine);
+		let inref = Rc::clone(&self.input);
 		let touchref = Rc::clone(&touches);
+		let vsplit = Rc::clone(&self.vsplit);
+		let mthrottle = Rc::clone(&mouse_throttle);
+		let mdown = Rc::clone(&mouse_down);
 		let ptrstartfn = move |event: web_sys::PointerEvent| {
-			let (_,id,x,pt) = open_pointer_fn(&event);
+			let (_,id,x,y,pt) = open_pointer_fn(&event);
 			let mut touches = (*touchref).borrow_mut();
 			let mut engine = (*engref).borrow_mut();
+			let mut input = (*inref).borrow_mut();
 			if is_touch(&pt) {
 				engine.set_block_alert(false);
-				touches.insert(id, x);
-				engine.set_thrust(touches.values().any(|e| { e < &vsplit }));
+				touches.insert(id, (x, y, y));
+				input.set_throttle(Source::Pointer, Self::touch_throttle(&touches, vsplit.get()));
 			} else {
-				engine.set_thrust(true);
+				mdown.set(true);
+				input.set_throttle(Source::Pointer, mthrottle.get());
 			}
 		};
 
-		let engref = Rc::clone(&self.engine);
+		let inref = Rc::clone(&self.input);
 		let touchref = Rc::clone(&touches);
+		let vsplit = Rc::clone(&self.vsplit);
+		let mdown = Rc::clone(&mouse_down);
 		let ptrendfn = move |event: web_sys::PointerEvent| {
-			let (_,id,_,pt) = open_pointer_fn(&event);
+			let (_,id,_,_,pt) = open_pointer_fn(&event);
 			let mut touches = (*touchref).borrow_mut();
-			let mut engine = (*engref).borrow_mut();
+			let mut input = (*inref).borrow_mut();
 			if is_touch(&pt) {
 				touches.remove(&id);
-				engine.set_thrust(touches.values().any(|e| { e < &vsplit }));
+				input.set_throttle(Source::Pointer, Self::touch_throttle(&touches, vsplit.get()));
 			} else {
-				engine.set_thrust(false);
+				mdown.set(false);
+				input.set_throttle(Source::Pointer, 0.0);
 			}
 		};
 
 		let engref = Rc::clone(&self.engine);
+		let inref = Rc::clone(&self.input);
 		let touchref = Rc::clone(&touches);
 		let ptrcancelfn = move |event: web_sys::PointerEvent| {
-			let (_,_,_,pt) = open_pointer_fn(&event);
+			let (_,_,_,_,pt) = open_pointer_fn(&event);
 			let mut touches = (*touchref).borrow_mut();
 			let mut engine = (*engref).borrow_mut();
+			let mut input = (*inref).borrow_mut();
 			if is_touch(&pt) {
 				engine.set_block_alert(true);
 				touches.clear();
-				engine.set_thrust(touches.values().any(|e| { e < &vsplit }));
+				input.set_throttle(Source::Pointer, 0.0);
+			}
+		};
+
+		// the wheel sets the throttle used by the mouse button
+		let inref = Rc::clone(&self.input);
+		let mthrottle = Rc::clone(&mouse_throttle);
+		let mdown = Rc::clone(&mouse_down);
+		let wheelfn = move |event: web_sys::WheelEvent| {
+			event.prevent_default();
+			let step = if event.delta_y() < 0.0 { 0.1 } else if event.delta_y() > 0.0 { -0.1 } else { 0.0 };
+			mthrottle.set(Fpt::max(0.1, Fpt::min(1.0, mthrottle.get() + step)));
+			if mdown.get() {
+				(*inref).borrow_mut().set_throttle(Source::Pointer, mthrottle.get());
 			}
 		};
 
@@ -518,20 +1026,22 @@ impl GameView {
 		attach!("ctrl", "pointerup", ptrendfn);
 
 		attach!("ctrl", "pointercancel", ptrcancelfn);
+		attach!("ctrl", "wheel", wheelfn);
 	}
 
 	#[allow(dead_code)]
 	fn setup_touch_events(&self) {
-		let swidth = window().inner_width().unwrap().as_f64().unwrap() as u32;
-		let vsplit = (swidth / 2) as i32;
+		let vsplit = Rc::clone(&self.vsplit);
 		let touches : Rc<RefCell<HashMap<i32,i32>>> = Rc::new(RefCell::new(HashMap::new()));
 		let engref = Rc::clone(&self.engine);
+		let inref = Rc::clone(&self.input);
 		let touchfn = move |event: web_sys::TouchEvent| {
 			event.prevent_default();
 			event.stop_propagation();
 			let evt = event.type_();
 			let mut touches = (*touches).borrow_mut();
 			let mut engine = (*engref).borrow_mut();
+			let mut input = (*inref).borrow_mut();
 			let mut thrusting = false;
 			let blocked = evt == "touchcancel";
 
@@ -545,7 +1055,7 @@ impl GameView {
 					let t = tl.item(n).unwrap();
 					let id = t.identifier();
 					let y = t.page_y();
-					let thrust = t.page_x() < vsplit;
+					let thrust = t.page_x() < vsplit.get();
 					thrusting = thrusting || thrust;
 
 					// dlog!(format!("ptr {} {:.2},{:.2} {}", id, t.page_x(), t.page_y(), thrusting).as_str());
@@ -567,12 +1077,12 @@ impl GameView {
 						}
 					}
 					if delta != 0 {
-						engine.rotate(delta);
+						input.rotate_by(delta as Deg);
 						// dlog!(format!("roll {} {}", delta, engine.rot).as_str());
 					}
 				}
 			}
-			engine.set_thrust(thrusting);
+			input.set(Source::Pointer, Action::Thrust, thrusting);
 			engine.set_block_alert(blocked);
 		};
 
@@ -583,25 +1093,41 @@ impl GameView {
 	}
 
 	fn setup_triggers(&mut self) {
-		const ENGINE_STEP_MS : u64 = 25u64;
 		let animf = Rc::new(RefCell::new(None));
 		let animfc = animf.clone();
 		let rootc = self.root.clone();
 		let config = self.config.clone();
 
 		let engref = Rc::clone(&self.engine);
+		let inref = Rc::clone(&self.input);
+		let mut gamepad = GamepadSource::new();
 		let arrow = Rc::clone(&self.arrow);
 		let background = Rc::clone(&self.background);
 		let canvas = canvas();
+		let vsplit = Rc::clone(&self.vsplit);
+		let resized = Rc::clone(&self.resized);
 
 		let mut fading = -1;
+		let mut paused = false;
 		let mut last_engine_run = js_sys::Date::now() as u64;
 
 		*animfc.borrow_mut() = Some(Closure::new(move || {
 			let mut engine = (*engref).borrow_mut();
+			let mut input = (*inref).borrow_mut();
 			let now = js_sys::Date::now() as u64;
 
-			if engine.block_alert() {
+			gamepad.poll(&mut input);
+			if input.take_pause() {
+				paused = !paused;
+			}
+			if input.take_restart() {
+				engine.restart();
+				paused = false;
+				fading = -1;
+				let _ = elem::<HtmlElement>("game").set_attribute("style", GAME_DIV_STYLE);
+			}
+
+			if engine.block_alert() || paused {
 				last_engine_run = now;
 			} else {
 				let target_step = now - ENGINE_STEP_MS;
@@ -623,11 +1149,18 @@ impl GameView {
 						fading += 1;
 					}
 
+					input.apply(&mut engine);
 					engine.move_step();
 				}
 			}
 
-			Self::draw(&canvas, &engine, &background, &arrow, 100 - fading);
+			if resized.replace(false) {
+				Self::layout(&canvas, &engine, &vsplit);
+			}
+			Self::draw(&canvas, &engine, &config, &background, &arrow, 100 - fading);
+			if paused {
+				Self::draw_paused(&canvas, &engine);
+			}
 			request_animation_frame(animf.borrow().as_ref().unwrap());
 		}));
 
@@ -636,6 +1169,7 @@ impl GameView {
 
 	pub fn show(&mut self) {
 		self.setup_html();
+		self.setup_resize_events();
 		self.setup_keyboard_events();
 		// self.setup_mouse_events();
 		// self.setup_touch_events();
@@ -0,0 +1,338 @@
+use wasm_bindgen::prelude::*;
+
+use crate::common::*;
+use crate::engine::GameEngine;
+use crate::geom::Deg;
+
+// degrees per engine step at full deflection, same as the digital keys
+const ROTATION_STEP: Fpt = 6.0;
+const STICK_DEADZONE: Fpt = 0.15;
+const TRIGGER_DEADZONE: Fpt = 0.05;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+	Thrust,
+	RotateLeft,
+	RotateRight,
+	Fire,
+	Pause,
+	Restart,
+}
+
+impl Action {
+	pub const ALL: [Action; 6] = [
+		Action::Thrust,
+		Action::RotateLeft,
+		Action::RotateRight,
+		Action::Fire,
+		Action::Pause,
+		Action::Restart,
+	];
+
+	// identifier used for persistence and html ids
+	pub fn name(&self) -> &'static str {
+		match self {
+			Action::Thrust => "thrust",
+			Action::RotateLeft => "left",
+			Action::RotateRight => "right",
+			Action::Fire => "fire",
+			Action::Pause => "pause",
+			Action::Restart => "restart",
+		}
+	}
+	pub fn label(&self) -> &'static str {
+		match self {
+			Action::Thrust => "Thrust",
+			Action::RotateLeft => "Rotate left",
+			Action::RotateRight => "Rotate right",
+			Action::Fire => "Fire",
+			Action::Pause => "Pause",
+			Action::Restart => "Restart",
+		}
+	}
+	fn from_name(name: &str) -> Option<Action> {
+		Self::ALL.iter().find(|a| a.name() == name).copied()
+	}
+}
+
+#[derive(Clone, Copy)]
+pub enum Source {
+	Keyboard = 0,
+	Pointer = 1,
+	Gamepad = 2,
+}
+const NUM_SOURCES: usize = 3;
+
+#[derive(Clone, Default)]
+struct SourceState {
+	thrust: bool,
+	throttle: Fpt,
+	left: bool,
+	right: bool,
+	rotate: Fpt,
+	fire: bool,
+	pause: bool,
+	restart: bool,
+}
+
+// Every input device feeds its own slot, the engine is driven by the
+// combination of all of them once per engine step.
+pub struct Input {
+	sources: [SourceState; NUM_SOURCES],
+	rotate_by: Deg,
+	pause: bool,
+	restart: bool,
+}
+
+impl Input {
+	pub fn new() -> Self {
+		Self {
+			sources: Default::default(),
+			rotate_by: 0.0,
+			pause: false,
+			restart: false,
+		}
+	}
+
+	pub fn set(&mut self, src: Source, action: Action, on: bool) {
+		let st = &mut self.sources[src as usize];
+		match action {
+			Action::Thrust => st.thrust = on,
+			Action::RotateLeft => st.left = on,
+			Action::RotateRight => st.right = on,
+			Action::Fire => st.fire = on,
+			Action::Pause => {
+				self.pause = self.pause || (on && !st.pause);
+				st.pause = on;
+			},
+			Action::Restart => {
+				self.restart = self.restart || (on && !st.restart);
+				st.restart = on;
+			},
+		}
+	}
+	// analog thrust, 0.0 to 1.0
+	pub fn set_throttle(&mut self, src: Source, v: Fpt) {
+		self.sources[src as usize].throttle = Fpt::max(0.0, Fpt::min(1.0, v));
+	}
+	// analog rotation, -1.0 (left) to 1.0 (right)
+	pub fn set_rotate(&mut self, src: Source, v: Fpt) {
+		self.sources[src as usize].rotate = Fpt::max(-1.0, Fpt::min(1.0, v));
+	}
+	// relative rotation in degrees, applied once
+	pub fn rotate_by(&mut self, delta: Deg) {
+		self.rotate_by += delta;
+	}
+	pub fn reset(&mut self, src: Source) {
+		self.sources[src as usize] = SourceState::default();
+	}
+
+	pub fn take_pause(&mut self) -> bool {
+		std::mem::replace(&mut self.pause, false)
+	}
+	pub fn take_restart(&mut self) -> bool {
+		std::mem::replace(&mut self.restart, false)
+	}
+
+	pub fn apply(&mut self, engine: &mut GameEngine) {
+		// digital thrust is full throttle
+		let throttle = self.sources.iter()
+			.map(|s| if s.thrust { 1.0 } else { s.throttle })
+			.fold(0.0, Fpt::max);
+		let left = self.sources.iter().any(|s| s.left);
+		let right = self.sources.iter().any(|s| s.right);
+		let analog: Fpt = self.sources.iter().map(|s| s.rotate).sum();
+
+		engine.set_throttle(throttle);
+		engine.set_firing(self.sources.iter().any(|s| s.fire));
+		engine.set_rotation(Some(left), Some(right));
+
+		if engine.has_inertia() {
+			// with the attitude model everything becomes an RCS command
+			let analog = analog + self.rotate_by / ROTATION_STEP;
+			self.rotate_by = 0.0;
+			engine.set_torque(analog);
+			return;
+		}
+
+		let delta = self.rotate_by + Fpt::max(-1.0, Fpt::min(1.0, analog)) * ROTATION_STEP;
+		self.rotate_by = 0.0;
+		if delta != 0.0 {
+			engine.rotate(delta);
+		}
+	}
+}
+
+// Polls the Gamepad API, must be called on every animation frame as
+// gamepads do not generate input events.
+pub struct GamepadSource {
+	active: bool,
+}
+
+impl GamepadSource {
+	// buttons and axes of the "standard" gamepad mapping
+	const BTN_A: u32 = 0;
+	const BTN_X: u32 = 2;
+	const BTN_LTRIGGER: u32 = 6;
+	const BTN_RTRIGGER: u32 = 7;
+	const BTN_SELECT: u32 = 8;
+	const BTN_START: u32 = 9;
+	const BTN_DPAD_LEFT: u32 = 14;
+	const BTN_DPAD_RIGHT: u32 = 15;
+	const AXIS_LEFT_X: u32 = 0;
+
+	pub fn new() -> Self {
+		Self { active: false }
+	}
+
+	fn first_gamepad() -> Option<web_sys::Gamepad> {
+		let pads = window().navigator().get_gamepads().ok()?;
+		pads.iter()
+			.filter_map(|p| p.dyn_into::<web_sys::Gamepad>().ok())
+			.find(|p| p.connected())
+	}
+
+	fn button(pad: &web_sys::Gamepad, idx: u32) -> Option<web_sys::GamepadButton> {
+		pad.buttons().get(idx).dyn_into::<web_sys::GamepadButton>().ok()
+	}
+	fn pressed(pad: &web_sys::Gamepad, idx: u32) -> bool {
+		Self::button(pad, idx).map(|b| b.pressed()).unwrap_or(false)
+	}
+	fn value(pad: &web_sys::Gamepad, idx: u32) -> Fpt {
+		Self::button(pad, idx).map(|b| b.value()).unwrap_or(0.0)
+	}
+
+	pub fn poll(&mut self, input: &mut Input) {
+		let pad = match Self::first_gamepad() {
+			Some(p) => p,
+			None => {
+				if self.active {
+					input.reset(Source::Gamepad);
+					self.active = false;
+				}
+				return;
+			}
+		};
+		self.active = true;
+
+		let stick = pad.axes().get(Self::AXIS_LEFT_X).as_f64().unwrap_or(0.0);
+		let stick = if stick.abs() < STICK_DEADZONE { 0.0 } else { stick };
+		let trigger = Fpt::max(Self::value(&pad, Self::BTN_RTRIGGER), Self::value(&pad, Self::BTN_LTRIGGER));
+		let trigger = if trigger < TRIGGER_DEADZONE { 0.0 } else { trigger };
+
+		input.set_rotate(Source::Gamepad, stick);
+		input.set_throttle(Source::Gamepad, trigger);
+		input.set(Source::Gamepad, Action::Thrust, Self::pressed(&pad, Self::BTN_A));
+		input.set(Source::Gamepad, Action::RotateLeft, Self::pressed(&pad, Self::BTN_DPAD_LEFT));
+		input.set(Source::Gamepad, Action::RotateRight, Self::pressed(&pad, Self::BTN_DPAD_RIGHT));
+		input.set(Source::Gamepad, Action::Fire, Self::pressed(&pad, Self::BTN_X));
+		input.set(Source::Gamepad, Action::Pause, Self::pressed(&pad, Self::BTN_START));
+		input.set(Source::Gamepad, Action::Restart, Self::pressed(&pad, Self::BTN_SELECT));
+	}
+}
+
+// Maps KeyboardEvent.code values (physical keys, so WASD stays in place
+// on AZERTY and other layouts) to actions.
+#[derive(Clone)]
+pub struct KeyBindings {
+	keys: Vec<(Action, Vec<String>)>,
+}
+
+impl KeyBindings {
+	const STORAGE_KEY: &'static str = "rusty_pilot.keys";
+	const MAX_KEYS: usize = 3;
+
+	pub fn default_bindings() -> Self {
+		let keys = |k: &[&str]| k.iter().map(|s| s.to_string()).collect::<Vec<String>>();
+		Self {
+			keys: vec![
+				(Action::Thrust, keys(&["ArrowUp", "KeyW", "Space"])),
+				(Action::RotateLeft, keys(&["ArrowLeft", "KeyA", "Numpad4"])),
+				(Action::RotateRight, keys(&["ArrowRight", "KeyD", "Numpad6"])),
+				(Action::Fire, keys(&["KeyF", "ControlLeft", "Numpad0"])),
+				(Action::Pause, keys(&["KeyP", "Escape"])),
+				(Action::Restart, keys(&["KeyR"])),
+			]
+		}
+	}
+
+	fn storage() -> Option<web_sys::Storage> {
+		window().local_storage().ok().flatten()
+	}
+
+	// stored as "thrust=ArrowUp,KeyW;left=ArrowLeft;..."
+	pub fn load() -> Self {
+		let mut rv = Self::default_bindings();
+		let stored = Self::storage().and_then(|s| s.get_item(Self::STORAGE_KEY).ok().flatten());
+		if let Some(stored) = stored {
+			for entry in stored.split(';') {
+				if let Some((name, codes)) = entry.split_once('=') {
+					if let Some(action) = Action::from_name(name) {
+						rv.set_keys(action, codes.split(',').filter(|c| !c.is_empty()).map(|c| c.to_string()).collect());
+					}
+				}
+			}
+		}
+		rv
+	}
+
+	pub fn save(&self) {
+		let value = self.keys.iter()
+			.map(|(a, k)| format!("{}={}", a.name(), k.join(",")))
+			.collect::<Vec<String>>()
+			.join(";");
+		if let Some(s) = Self::storage() {
+			let _ = s.set_item(Self::STORAGE_KEY, value.as_str());
+		}
+	}
+
+	pub fn reset() {
+		if let Some(s) = Self::storage() {
+			let _ = s.remove_item(Self::STORAGE_KEY);
+		}
+	}
+
+	fn set_keys(&mut self, action: Action, keys: Vec<String>) {
+		for (a, k) in self.keys.iter_mut() {
+			if *a == action {
+				*k = keys;
+				return;
+			}
+		}
+	}
+
+	pub fn keys(&self, action: Action) -> &[String] {
+		self.keys.iter()
+			.find(|(a, _)| *a == action)
+			.map(|(_, k)| k.as_slice())
+			.unwrap_or(&[])
+	}
+
+	pub fn action(&self, code: &str) -> Option<Action> {
+		self.keys.iter()
+			.find(|(_, k)| k.iter().any(|c| c == code))
+			.map(|(a, _)| *a)
+	}
+
+	// the new key becomes the first binding of the action, stealing it
+	// from any other action it was bound to
+	pub fn bind(&mut self, action: Action, code: &str) {
+		for (_, k) in self.keys.iter_mut() {
+			k.retain(|c| c != code);
+		}
+		for (a, k) in self.keys.iter_mut() {
+			if *a == action {
+				k.insert(0, code.to_string());
+				k.truncate(Self::MAX_KEYS);
+			}
+		}
+	}
+
+	pub fn key_label(code: &str) -> String {
+		let label = code.strip_prefix("Key")
+			.or_else(|| code.strip_prefix("Digit"))
+			.or_else(|| code.strip_prefix("Arrow"))
+			.unwrap_or(code);
+		label.to_uppercase()
+	}
+}
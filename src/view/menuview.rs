@@ -5,11 +5,15 @@ use std::rc::Rc;
 use std::cell::RefCell;
 
 use crate::view::gameview::GameView;
+use crate::view::controlsview::ControlsView;
+use crate::view::input::KeyBindings;
 use crate::levels::GameData;
 use crate::rand::Random;
 use crate::common::*;
 use crate::attach;
 
+const NUM_MAPS: u32 = 17;
+
 #[derive(Clone)]
 pub struct MenuViewData {
 	map: u32,
@@ -18,6 +22,25 @@ pub struct MenuViewData {
 	thrust: u32,
 	gravity: u32,
 	friction: u32,
+	handling: u32,
+	mass: u32,
+	weapons: u32,
+	assist: u32,
+	hud: u32,
+	// shared by every view, the controls view edits them in place
+	bindings: Rc<RefCell<KeyBindings>>,
+}
+
+impl MenuViewData {
+	pub fn trajectory(&self) -> bool {
+		self.assist > 0
+	}
+	pub fn telemetry(&self) -> bool {
+		self.hud > 0
+	}
+	pub fn bindings(&self) -> Rc<RefCell<KeyBindings>> {
+		Rc::clone(&self.bindings)
+	}
 }
 
 pub struct MenuView {
@@ -61,6 +84,12 @@ impl MenuView {
 				<div class=\"menusep\"></div>
 				<div id=\"fuel\" class=\"menuitem\">{}</div>\
 				<div id=\"thrust\" class=\"menuitem\">{}</div>\
+				<div id=\"handling\" class=\"menuitem\">{}</div>\
+				<div id=\"mass\" class=\"menuitem\">{}</div>\
+				<div id=\"weapons\" class=\"menuitem\">{}</div>\
+				<div class=\"menusep\"></div>
+				<div id=\"assist\" class=\"menuitem\">{}</div>\
+				<div id=\"hud\" class=\"menuitem\">{}</div>\
 				<div class=\"buttons\">\
 					<div id=\"rand\" class=\"button\">\
 						RANDOM\
@@ -68,6 +97,9 @@ impl MenuView {
 					<div id=\"play\" class=\"button\">\
 						PLAY\
 					</div>\
+					<div id=\"controls\" class=\"button\">\
+						CONTROLS\
+					</div>\
 				</div>\
 			</div>\
 			<div id=\"console\" class=\"log\" \
@@ -80,7 +112,12 @@ impl MenuView {
 			Self::label_gravity(data.gravity),
 			Self::label_friction(data.friction),
 			Self::label_fuel(data.fuel),
-			Self::label_thrust(data.thrust)
+			Self::label_thrust(data.thrust),
+			Self::label_handling(data.handling),
+			Self::label_mass(data.mass),
+			Self::label_weapons(data.weapons),
+			Self::label_assist(data.assist),
+			Self::label_hud(data.hud)
 			).as_str());
 	}
 
@@ -92,16 +129,28 @@ impl MenuView {
 			asteroids: 2u32,
 			fuel: 2u32,
 			thrust: 1u32,
+			handling: 0u32,
+			mass: 0u32,
+			weapons: 0u32,
+			assist: 0u32,
+			hud: 0u32,
+			bindings: Rc::new(RefCell::new(KeyBindings::load())),
 		}
 	}
-	pub fn rand_data(rng: &mut Random) -> MenuViewData {
+	pub fn rand_data(rng: &mut Random, prev: &MenuViewData) -> MenuViewData {
 		MenuViewData {
-			map: rng.rand(9) as u32,
+			map: rng.rand(NUM_MAPS as i32) as u32,
 			gravity: rng.rand(5) as u32,
 			friction: rng.rand(5) as u32,
 			asteroids: rng.rand(5) as u32,
 			fuel: rng.rand(4) as u32,
 			thrust: rng.rand(4) as u32,
+			handling: prev.handling,
+			mass: prev.mass,
+			weapons: prev.weapons,
+			assist: prev.assist,
+			hud: prev.hud,
+			bindings: prev.bindings(),
 		}
 	}
 
@@ -116,6 +165,14 @@ impl MenuView {
 			6 => "<span>Map: UP</span>",
 			7 => "<span>Map: HUGE</span>",
 			8 => "<span>Map: GEARS</span>",
+			9 => "<span>Map: PLANETOID</span>",
+			10 => "<span>Map: MACHINERY</span>",
+			11 => "<span>Map: FERRY</span>",
+			12 => "<span>Map: RELAY</span>",
+			13 => "<span>Map: DELIVERY</span>",
+			14 => "<span>Map: FORTRESS</span>",
+			15 => "<span>Map: VAULT</span>",
+			16 => "<span>Map: WARP</span>",
 			_ => "<span>Map: SIMPLE</span>",
 		}
 	}
@@ -147,6 +204,50 @@ impl MenuView {
 			_ => "<span>Fuel: AVERAGE</span>",
 		}
 	}
+	fn label_handling(v: u32) -> &'static str {
+		match v {
+			0 => "<span>Handling: ARCADE</span>",
+			1 => "<span>Handling: ASSISTED</span>",
+			2 => "<span>Handling: MANUAL</span>",
+			_ => "<span>Handling: ARCADE</span>",
+		}
+	}
+	fn label_mass(v: u32) -> &'static str {
+		match v {
+			0 => "<span>Mass: OFF</span>",
+			1 => "<span>Mass: FUEL</span>",
+			_ => "<span>Mass: OFF</span>",
+		}
+	}
+	fn label_weapons(v: u32) -> &'static str {
+		match v {
+			0 => "<span>Weapons: OFF</span>",
+			1 => "<span>Weapons: LIMITED</span>",
+			2 => "<span>Weapons: PLENTY</span>",
+			_ => "<span>Weapons: OFF</span>",
+		}
+	}
+	fn ammo_default(v: u32) -> u32 {
+		match v {
+			1 => 20,
+			2 => 100,
+			_ => 0,
+		}
+	}
+	fn label_assist(v: u32) -> &'static str {
+		match v {
+			0 => "<span>Assist: OFF</span>",
+			1 => "<span>Assist: TRAJECTORY</span>",
+			_ => "<span>Assist: OFF</span>",
+		}
+	}
+	fn label_hud(v: u32) -> &'static str {
+		match v {
+			0 => "<span>Hud: BASIC</span>",
+			1 => "<span>Hud: TELEMETRY</span>",
+			_ => "<span>Hud: BASIC</span>",
+		}
+	}
 	fn label_gravity(v: u32) -> &'static str {
 		match v {
 			0 => "<span>Gravity: OFF</span>",
@@ -174,7 +275,7 @@ impl MenuView {
 			event.prevent_default();
 			let mut data = data.borrow_mut();
 			let v = (*data).map;
-			let v = (v + 1) % 9;
+			let v = (v + 1) % NUM_MAPS;
 			(*data).map = v;
 			elem::<HtmlElement>("map").set_inner_html(Self::label_map(v));
         });
@@ -229,12 +330,58 @@ impl MenuView {
 			elem::<HtmlElement>("friction").set_inner_html(Self::label_friction(v));
         });
 
+		let data = Rc::clone(&self.data);
+		attach!("handling", evt, move |event: web_sys::Event| {
+			event.prevent_default();
+			let mut data = data.borrow_mut();
+			let v = (data.handling + 1) % 3;
+			data.handling = v;
+			elem::<HtmlElement>("handling").set_inner_html(Self::label_handling(v));
+        });
+
+		let data = Rc::clone(&self.data);
+		attach!("mass", evt, move |event: web_sys::Event| {
+			event.prevent_default();
+			let mut data = data.borrow_mut();
+			let v = (data.mass + 1) % 2;
+			data.mass = v;
+			elem::<HtmlElement>("mass").set_inner_html(Self::label_mass(v));
+        });
+
+		let data = Rc::clone(&self.data);
+		attach!("weapons", evt, move |event: web_sys::Event| {
+			event.prevent_default();
+			let mut data = data.borrow_mut();
+			let v = (data.weapons + 1) % 3;
+			data.weapons = v;
+			elem::<HtmlElement>("weapons").set_inner_html(Self::label_weapons(v));
+        });
+
+		let data = Rc::clone(&self.data);
+		attach!("assist", evt, move |event: web_sys::Event| {
+			event.prevent_default();
+			let mut data = data.borrow_mut();
+			let v = (data.assist + 1) % 2;
+			data.assist = v;
+			elem::<HtmlElement>("assist").set_inner_html(Self::label_assist(v));
+        });
+
+		let data = Rc::clone(&self.data);
+		attach!("hud", evt, move |event: web_sys::Event| {
+			event.prevent_default();
+			let mut data = data.borrow_mut();
+			let v = (data.hud + 1) % 2;
+			data.hud = v;
+			elem::<HtmlElement>("hud").set_inner_html(Self::label_hud(v));
+        });
+
 		let data = Rc::clone(&self.data);
         let rng = Rc::clone(&self.rng);
 		attach!("rand", evt, move |event: web_sys::Event| {
 			event.prevent_default();
 			let mut rng = rng.borrow_mut();
-            data.replace(Self::rand_data(&mut rng));
+            let rd = Self::rand_data(&mut rng, &data.borrow());
+            data.replace(rd);
             let data = data.borrow();
 			elem::<HtmlElement>("map").set_inner_html(Self::label_map(data.map));
 			elem::<HtmlElement>("asteroids").set_inner_html(Self::label_asteroids(data.asteroids));
@@ -253,21 +400,43 @@ impl MenuView {
 			let fr = (*data).friction;
 			let th = (*data).thrust;
 			let gravity = (*data).gravity;
+			let hd = data.handling;
 			let gd : GameData = match (*data).map {
-				0 => GameData::simple(ast, th, fuel, gravity, fr),
-				1 => GameData::cave(ast, th, fuel, gravity, fr),
-				2 => GameData::windy_pillars(ast, th, fuel, gravity, fr),
-				3 => GameData::tunnel(ast, th, fuel, gravity, fr),
-				4 => GameData::shifted(ast, th, fuel, gravity, fr),
-				5 => GameData::choice(ast, th, fuel, gravity, fr),
-				6 => GameData::up(ast, th, fuel, gravity, fr),
-				7 => GameData::huge(ast, th, fuel, gravity, fr),
-				8 => GameData::gears(ast, th, fuel, gravity, fr),
-				_ => GameData::simple(ast, th, fuel, gravity, fr),
+				0 => GameData::simple(ast, th, fuel, gravity, fr, hd),
+				1 => GameData::cave(ast, th, fuel, gravity, fr, hd),
+				2 => GameData::windy_pillars(ast, th, fuel, gravity, fr, hd),
+				3 => GameData::tunnel(ast, th, fuel, gravity, fr, hd),
+				4 => GameData::shifted(ast, th, fuel, gravity, fr, hd),
+				5 => GameData::choice(ast, th, fuel, gravity, fr, hd),
+				6 => GameData::up(ast, th, fuel, gravity, fr, hd),
+				7 => GameData::huge(ast, th, fuel, gravity, fr, hd),
+				8 => GameData::gears(ast, th, fuel, gravity, fr, hd),
+				9 => GameData::planetoid_map(ast, th, fuel, gravity, fr, hd),
+				10 => GameData::machinery(ast, th, fuel, gravity, fr, hd),
+				11 => GameData::ferry(ast, th, fuel, gravity, fr, hd),
+				12 => GameData::relay(ast, th, fuel, gravity, fr, hd),
+				13 => GameData::delivery(ast, th, fuel, gravity, fr, hd),
+				14 => GameData::fortress(ast, th, fuel, gravity, fr, hd),
+				15 => GameData::vault(ast, th, fuel, gravity, fr, hd),
+				16 => GameData::warp(ast, th, fuel, gravity, fr, hd),
+				_ => GameData::simple(ast, th, fuel, gravity, fr, hd),
 			};
+			let gd = gd.armed(Self::ammo_default(data.weapons))
+				.with_fuel_mass(if data.mass > 0 { 1.0 } else { 0.0 });
 
 			Self::to_game(gd, (*data).clone());
         });
+
+		let data = Rc::clone(&self.data);
+		attach!("controls", evt, move |event: web_sys::Event| {
+			event.prevent_default();
+			Self::to_controls(data.borrow().clone());
+        });
+	}
+
+	fn to_controls(cfg: MenuViewData) {
+		let view = ControlsView::new(target_elem(), cfg);
+		view.show();
 	}
 
 	fn to_game(gd : GameData, cfg: MenuViewData) {